@@ -3,7 +3,10 @@
 //! Terseid supports hierarchical child IDs by appending dot-separated numbers to a parent ID.
 //! For example, `child_id("bd-a7x", 1)` returns `"bd-a7x.1"`, and `child_id("bd-a7x.1", 3)` returns `"bd-a7x.1.3"`.
 
-use crate::parse::parse_id;
+use crate::error::{ChildPolicyViolation, Result, TerseIdError};
+use crate::parse::{ParsedId, parse_id};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
 
 /// Creates a child ID from a parent ID and child number.
 ///
@@ -24,6 +27,150 @@ pub fn child_id(parent_id: &str, child_number: u32) -> String {
     format!("{parent_id}.{child_number}")
 }
 
+/// Limits on the shape of child paths.
+///
+/// The default policy accepts everything `parse_id` accepts. Tighten it with
+/// the builder methods and enforce it with [`try_child_id`],
+/// [`parse_id_with_policy`](crate::parse::parse_id_with_policy) or
+/// [`ChildAllocator::policy`].
+///
+/// # Examples
+///
+/// ```
+/// use terseid::children::{ChildPolicy, try_child_id};
+///
+/// let policy = ChildPolicy::new().max_depth(2).allow_zero(false);
+/// assert_eq!(try_child_id("bd-a7x.1", 3, &policy).unwrap(), "bd-a7x.1.3");
+/// assert!(try_child_id("bd-a7x.1.3", 1, &policy).is_err()); // too deep
+/// assert!(try_child_id("bd-a7x", 0, &policy).is_err());     // zero
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChildPolicy {
+    /// Maximum number of child path segments, or `None` for unlimited.
+    pub max_depth: Option<usize>,
+    /// Whether `0` is a valid child number.
+    pub allow_zero: bool,
+    /// Largest valid child number, or `None` for `u32::MAX`.
+    pub max_child_number: Option<u32>,
+    /// Whether segments like `01` are accepted when parsing.
+    pub allow_leading_zeros: bool,
+}
+
+impl ChildPolicy {
+    /// Creates a permissive policy that accepts every valid child path.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_depth: None,
+            allow_zero: true,
+            max_child_number: None,
+            allow_leading_zeros: true,
+        }
+    }
+
+    /// Limits child paths to `depth` segments.
+    #[must_use]
+    pub const fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Sets whether `0` is a valid child number.
+    #[must_use]
+    pub const fn allow_zero(mut self, allow: bool) -> Self {
+        self.allow_zero = allow;
+        self
+    }
+
+    /// Limits child numbers to at most `max`.
+    #[must_use]
+    pub const fn max_child_number(mut self, max: u32) -> Self {
+        self.max_child_number = Some(max);
+        self
+    }
+
+    /// Sets whether segments like `01` are accepted when parsing.
+    #[must_use]
+    pub const fn allow_leading_zeros(mut self, allow: bool) -> Self {
+        self.allow_leading_zeros = allow;
+        self
+    }
+
+    /// Checks a parsed child path against this policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first rule the path breaks.
+    pub fn check(&self, child_path: &[u32]) -> core::result::Result<(), ChildPolicyViolation> {
+        if let Some(max_depth) = self.max_depth
+            && child_path.len() > max_depth
+        {
+            return Err(ChildPolicyViolation::TooDeep {
+                depth: child_path.len(),
+                max_depth,
+            });
+        }
+        for &number in child_path {
+            self.check_number(number)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the raw text of one child segment for leading zeros.
+    pub(crate) fn check_segment_text(
+        &self,
+        segment: &str,
+    ) -> core::result::Result<(), ChildPolicyViolation> {
+        if !self.allow_leading_zeros && segment.len() > 1 && segment.starts_with('0') {
+            return Err(ChildPolicyViolation::LeadingZero {
+                segment: String::from(segment),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) const fn check_number(
+        &self,
+        number: u32,
+    ) -> core::result::Result<(), ChildPolicyViolation> {
+        if number == 0 && !self.allow_zero {
+            return Err(ChildPolicyViolation::ZeroChild);
+        }
+        if let Some(max) = self.max_child_number
+            && number > max
+        {
+            return Err(ChildPolicyViolation::ChildNumberTooLarge { number, max });
+        }
+        Ok(())
+    }
+}
+
+impl Default for ChildPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a child ID after validating the parent and the policy.
+///
+/// Unlike [`child_id`], the parent must parse, and the result is the
+/// normalized (lowercased) child ID.
+///
+/// # Errors
+///
+/// Returns `InvalidId` if `parent_id` cannot be parsed.
+/// Returns `PolicyViolation` if the child would break `policy`.
+pub fn try_child_id(parent_id: &str, child_number: u32, policy: &ChildPolicy) -> Result<String> {
+    let mut child = parse_id(parent_id)?;
+    child.child_path.push(child_number);
+    let id = child.to_id_string();
+    match policy.check(&child.child_path) {
+        Ok(()) => Ok(id),
+        Err(violation) => Err(TerseIdError::PolicyViolation { id, violation }),
+    }
+}
+
 /// Checks if an ID is a child ID (has a child path).
 ///
 /// Returns true if the ID contains one or more dot-separated child path segments.
@@ -63,10 +210,229 @@ pub fn id_depth(id: &str) -> usize {
     parse_id(id).map_or(0, |parsed| parsed.depth())
 }
 
+/// How [`ChildAllocator`] picks the next child number.
+///
+/// Child numbers start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChildNumbering {
+    /// One more than the highest number in use. Never reuses numbers.
+    #[default]
+    MaxPlusOne,
+    /// The lowest number not in use, reusing gaps left by deleted children.
+    FillGaps,
+    /// Children must be numbered `1..=n` without gaps; the next is `n + 1`.
+    ///
+    /// Allocation fails with `ChildNumberingGap` if a number is missing.
+    Dense,
+}
+
+/// Allocates child numbers under a single parent.
+///
+/// Feed it the existing IDs under the parent with [`observe`](Self::observe),
+/// then call [`allocate`](Self::allocate). Allocated numbers are reserved, so
+/// repeated calls never hand out the same child twice.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::children::{ChildAllocator, ChildNumbering};
+///
+/// let mut allocator = ChildAllocator::new("bd-a7x")
+///     .unwrap()
+///     .numbering(ChildNumbering::FillGaps);
+/// allocator.observe_all(&["bd-a7x.1", "bd-a7x.3", "bd-a7x.3.1"]).unwrap();
+///
+/// assert_eq!(allocator.allocate().unwrap(), "bd-a7x.2");
+/// assert_eq!(allocator.allocate().unwrap(), "bd-a7x.4");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChildAllocator {
+    parent: ParsedId,
+    numbering: ChildNumbering,
+    policy: ChildPolicy,
+    taken: BTreeSet<u32>,
+}
+
+impl ChildAllocator {
+    /// Creates an allocator for children of `parent_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidId` if `parent_id` cannot be parsed.
+    pub fn new(parent_id: &str) -> Result<Self> {
+        Ok(Self {
+            parent: parse_id(parent_id)?,
+            numbering: ChildNumbering::default(),
+            policy: ChildPolicy::new(),
+            taken: BTreeSet::new(),
+        })
+    }
+
+    /// Sets the numbering policy.
+    #[must_use]
+    pub const fn numbering(mut self, numbering: ChildNumbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Sets the child policy that allocated IDs must satisfy.
+    ///
+    /// `max_child_number` caps allocation; `allow_zero` has no effect since
+    /// numbering starts at 1.
+    #[must_use]
+    pub const fn policy(mut self, policy: ChildPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the parsed parent ID.
+    #[must_use]
+    pub const fn parent(&self) -> &ParsedId {
+        &self.parent
+    }
+
+    /// Records an existing ID under the parent.
+    ///
+    /// Direct children take their own number. Deeper descendants (e.g.
+    /// `bd-a7x.3.1` under `bd-a7x`) take the number of the child they live under.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidId` if `id` cannot be parsed.
+    /// Returns `NotAChild` if `id` is not a descendant of the parent.
+    pub fn observe(&mut self, id: &str) -> Result<()> {
+        let parsed = parse_id(id)?;
+        let depth = self.parent.depth();
+        if parsed.prefix != self.parent.prefix
+            || parsed.hash != self.parent.hash
+            || parsed.depth() <= depth
+            || parsed.child_path[..depth] != self.parent.child_path[..]
+        {
+            return Err(TerseIdError::NotAChild {
+                parent: self.parent.to_id_string(),
+                id: parsed.to_id_string(),
+            });
+        }
+        self.taken.insert(parsed.child_path[depth]);
+        Ok(())
+    }
+
+    /// Records every ID in `ids`. See [`observe`](Self::observe).
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`observe`](Self::observe).
+    pub fn observe_all(&mut self, ids: &[impl AsRef<str>]) -> Result<()> {
+        ids.iter().try_for_each(|id| self.observe(id.as_ref()))
+    }
+
+    /// Returns the number the next [`allocate`](Self::allocate) call would use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ChildNumberingGap` under `Dense` numbering if a number is missing.
+    /// Returns `ChildNumbersExhausted` if no number is left under the policy's maximum.
+    /// Returns `PolicyViolation` if children of the parent would be too deep.
+    pub fn next_number(&self) -> Result<u32> {
+        if let Some(max_depth) = self.policy.max_depth
+            && self.parent.depth() >= max_depth
+        {
+            let mut child = self.parent.clone();
+            child.child_path.push(1);
+            return Err(TerseIdError::PolicyViolation {
+                id: child.to_id_string(),
+                violation: ChildPolicyViolation::TooDeep {
+                    depth: child.depth(),
+                    max_depth,
+                },
+            });
+        }
+
+        let next = match self.numbering {
+            ChildNumbering::MaxPlusOne => match self.taken.last() {
+                Some(&max) => max.checked_add(1),
+                None => Some(1),
+            },
+            ChildNumbering::FillGaps => self.lowest_free(),
+            ChildNumbering::Dense => {
+                let next = self.lowest_free();
+                if let Some(missing) = next
+                    && self.taken.range(missing..).next().is_some()
+                {
+                    return Err(TerseIdError::ChildNumberingGap {
+                        parent: self.parent.to_id_string(),
+                        missing,
+                    });
+                }
+                next
+            }
+        };
+
+        let max = self.policy.max_child_number.unwrap_or(u32::MAX);
+        next.filter(|&n| n <= max)
+            .ok_or_else(|| TerseIdError::ChildNumbersExhausted {
+                parent: self.parent.to_id_string(),
+            })
+    }
+
+    /// Reserves the next child number and returns the child ID.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`next_number`](Self::next_number).
+    pub fn allocate(&mut self) -> Result<String> {
+        let number = self.next_number()?;
+        self.taken.insert(number);
+        let mut child = self.parent.clone();
+        child.child_path.push(number);
+        Ok(child.to_id_string())
+    }
+
+    /// Lowest number >= 1 that is not taken.
+    fn lowest_free(&self) -> Option<u32> {
+        let mut candidate = 1u32;
+        for &n in self.taken.range(1..) {
+            if n != candidate {
+                break;
+            }
+            candidate = candidate.checked_add(1)?;
+        }
+        Some(candidate)
+    }
+}
+
+/// Returns the next free child ID under `parent_id`, given the IDs that exist.
+///
+/// Uses [`ChildNumbering::MaxPlusOne`]. For other policies, or to allocate
+/// several children in a row, use [`ChildAllocator`].
+///
+/// # Examples
+///
+/// ```
+/// use terseid::children::next_child_id;
+///
+/// let existing = ["bd-a7x.1", "bd-a7x.2", "bd-a7x.2.1"];
+/// assert_eq!(next_child_id("bd-a7x", &existing).unwrap(), "bd-a7x.3");
+/// assert_eq!(next_child_id("bd-a7x.2", &["bd-a7x.2.1"]).unwrap(), "bd-a7x.2.2");
+/// ```
+///
+/// # Errors
+///
+/// Returns `InvalidId` if the parent or an existing ID cannot be parsed.
+/// Returns `NotAChild` if an existing ID is not a descendant of the parent.
+/// Returns `ChildNumbersExhausted` if the highest child is already `u32::MAX`.
+pub fn next_child_id(parent_id: &str, existing_ids: &[impl AsRef<str>]) -> Result<String> {
+    let mut allocator = ChildAllocator::new(parent_id)?;
+    allocator.observe_all(existing_ids)?;
+    allocator.allocate()
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
 
     // ========== child_id tests ==========
 
@@ -240,6 +606,224 @@ mod tests {
         assert_eq!(id_depth(&id), 1);
     }
 
+    // ========== next_child_id / ChildAllocator tests ==========
+
+    #[test]
+    fn test_next_child_id_no_children() {
+        let none: [&str; 0] = [];
+        assert_eq!(next_child_id("bd-a7x", &none).unwrap(), "bd-a7x.1");
+    }
+
+    #[test]
+    fn test_next_child_id_max_plus_one() {
+        let existing = ["bd-a7x.1", "bd-a7x.5", "bd-a7x.2"];
+        assert_eq!(next_child_id("bd-a7x", &existing).unwrap(), "bd-a7x.6");
+    }
+
+    #[test]
+    fn test_next_child_id_descendants_count_as_taken() {
+        let existing = ["bd-a7x.1", "bd-a7x.4.2.1"];
+        assert_eq!(next_child_id("bd-a7x", &existing).unwrap(), "bd-a7x.5");
+    }
+
+    #[test]
+    fn test_next_child_id_nested_parent() {
+        let existing = ["bd-a7x.3.1", "bd-a7x.3.2"];
+        assert_eq!(next_child_id("BD-A7X.3", &existing).unwrap(), "bd-a7x.3.3");
+    }
+
+    #[test]
+    fn test_next_child_id_rejects_non_children() {
+        for bad in ["bd-k2m.1", "tk-a7x.1", "bd-a7x", "bd-a7x.2.1"] {
+            let err = next_child_id("bd-a7x.3", &[bad]).unwrap_err();
+            assert!(
+                matches!(err, TerseIdError::NotAChild { .. }),
+                "{bad}: {err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_child_id_invalid_input() {
+        assert!(matches!(
+            next_child_id("not an id", &["bd-a7x.1"]),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+        assert!(matches!(
+            next_child_id("bd-a7x", &["bd-a7x.x"]),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_next_child_id_exhausted() {
+        let max = format!("bd-a7x.{}", u32::MAX);
+        assert_eq!(
+            next_child_id("bd-a7x", &[max]),
+            Err(TerseIdError::ChildNumbersExhausted {
+                parent: "bd-a7x".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_allocator_fill_gaps() {
+        let mut allocator = ChildAllocator::new("bd-a7x")
+            .unwrap()
+            .numbering(ChildNumbering::FillGaps);
+        allocator
+            .observe_all(&["bd-a7x.0", "bd-a7x.2", "bd-a7x.4"])
+            .unwrap();
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.1");
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.3");
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.5");
+    }
+
+    #[test]
+    fn test_allocator_max_plus_one_reserves() {
+        let mut allocator = ChildAllocator::new("bd-a7x").unwrap();
+        allocator.observe("bd-a7x.2").unwrap();
+        assert_eq!(allocator.next_number().unwrap(), 3);
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.3");
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.4");
+    }
+
+    #[test]
+    fn test_allocator_dense() {
+        let mut allocator = ChildAllocator::new("bd-a7x")
+            .unwrap()
+            .numbering(ChildNumbering::Dense);
+        allocator.observe_all(&["bd-a7x.1", "bd-a7x.2"]).unwrap();
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.3");
+
+        allocator.observe("bd-a7x.5").unwrap();
+        assert_eq!(
+            allocator.allocate(),
+            Err(TerseIdError::ChildNumberingGap {
+                parent: "bd-a7x".to_string(),
+                missing: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_allocator_parent_accessor() {
+        let allocator = ChildAllocator::new("bd-a7x.1").unwrap();
+        assert_eq!(allocator.parent().to_id_string(), "bd-a7x.1");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_numbering_serde_roundtrip() {
+        let json = serde_json::to_string(&ChildNumbering::FillGaps).unwrap();
+        assert_eq!(json, r#""FillGaps""#);
+        let back: ChildNumbering = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, ChildNumbering::FillGaps);
+    }
+
+    // ========== ChildPolicy / try_child_id tests ==========
+
+    #[test]
+    fn test_child_policy_default_is_permissive() {
+        let policy = ChildPolicy::default();
+        assert_eq!(policy, ChildPolicy::new());
+        assert!(policy.check(&[0, u32::MAX, 1, 2, 3, 4, 5, 6]).is_ok());
+        assert!(policy.check_segment_text("007").is_ok());
+    }
+
+    #[test]
+    fn test_child_policy_check() {
+        let policy = ChildPolicy::new()
+            .max_depth(2)
+            .allow_zero(false)
+            .max_child_number(99);
+        assert!(policy.check(&[1, 99]).is_ok());
+        assert_eq!(
+            policy.check(&[1, 2, 3]),
+            Err(ChildPolicyViolation::TooDeep {
+                depth: 3,
+                max_depth: 2
+            })
+        );
+        assert_eq!(policy.check(&[0]), Err(ChildPolicyViolation::ZeroChild));
+        assert_eq!(
+            policy.check(&[100]),
+            Err(ChildPolicyViolation::ChildNumberTooLarge {
+                number: 100,
+                max: 99
+            })
+        );
+    }
+
+    #[test]
+    fn test_child_policy_leading_zeros() {
+        let policy = ChildPolicy::new().allow_leading_zeros(false);
+        assert!(policy.check_segment_text("0").is_ok());
+        assert!(policy.check_segment_text("10").is_ok());
+        assert_eq!(
+            policy.check_segment_text("01"),
+            Err(ChildPolicyViolation::LeadingZero {
+                segment: "01".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_try_child_id_ok() {
+        let policy = ChildPolicy::new();
+        assert_eq!(try_child_id("BD-A7X", 1, &policy).unwrap(), "bd-a7x.1");
+        assert_eq!(try_child_id("bd-a7x.1", 3, &policy).unwrap(), "bd-a7x.1.3");
+    }
+
+    #[test]
+    fn test_try_child_id_invalid_parent() {
+        let policy = ChildPolicy::new();
+        assert!(matches!(
+            try_child_id("not an id", 1, &policy),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+        assert!(matches!(
+            try_child_id("bd-a7x.x", 1, &policy),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_try_child_id_policy_violation() {
+        let policy = ChildPolicy::new().max_depth(1);
+        assert_eq!(
+            try_child_id("bd-a7x.1", 2, &policy),
+            Err(TerseIdError::PolicyViolation {
+                id: "bd-a7x.1.2".to_string(),
+                violation: ChildPolicyViolation::TooDeep {
+                    depth: 2,
+                    max_depth: 1
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_allocator_respects_policy() {
+        let mut allocator = ChildAllocator::new("bd-a7x")
+            .unwrap()
+            .policy(ChildPolicy::new().max_child_number(2));
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.1");
+        assert_eq!(allocator.allocate().unwrap(), "bd-a7x.2");
+        assert!(matches!(
+            allocator.allocate(),
+            Err(TerseIdError::ChildNumbersExhausted { .. })
+        ));
+
+        let deep = ChildAllocator::new("bd-a7x.1")
+            .unwrap()
+            .policy(ChildPolicy::new().max_depth(1));
+        assert!(matches!(
+            deep.next_number(),
+            Err(TerseIdError::PolicyViolation { .. })
+        ));
+    }
+
     // ========== Integration tests ==========
 
     #[test]
@@ -1,3 +1,6 @@
+use alloc::string::String;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IdConfig {
     pub prefix: String,
     pub min_hash_length: usize,
@@ -71,6 +74,22 @@ mod tests {
         assert_eq!(config.max_collision_prob, 0.5);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let config = IdConfig::new("tk").min_hash_length(4);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"prefix":"tk","min_hash_length":4,"max_hash_length":8,"max_collision_prob":0.25}"#
+        );
+        let back: IdConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.prefix, "tk");
+        assert_eq!(back.min_hash_length, 4);
+        assert_eq!(back.max_hash_length, 8);
+        assert_eq!(back.max_collision_prob, 0.25);
+    }
+
     #[test]
     fn test_inverted_min_max_still_generates() {
         // When min > max, optimal_length loop range is empty,
@@ -1,34 +1,316 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TerseIdError {
-    #[error("invalid ID format: {id}")]
-    InvalidId { id: String },
+    #[error("invalid ID format: {id} ({reason})")]
+    InvalidId { id: String, reason: InvalidIdReason },
 
     #[error("prefix mismatch: expected '{expected}', found '{found}'")]
     PrefixMismatch { expected: String, found: String },
 
-    #[error("ambiguous ID '{partial}': matches {matches:?}")]
+    /// `matches` holds the best candidates, best first, and may be capped;
+    /// `total` counts every candidate.
+    #[error("ambiguous ID '{partial}': {}", CandidateList::new(.matches, *.total))]
     AmbiguousId {
         partial: String,
         matches: Vec<String>,
+        total: usize,
     },
 
     #[error("ID not found: {id}")]
     NotFound { id: String },
+
+    #[error("ID not found: {id}; did you mean {}?", .suggestions.join(", "))]
+    NotFoundWithSuggestions {
+        id: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("'{id}' is not a child of '{parent}'")]
+    NotAChild { parent: String, id: String },
+
+    #[error("children of '{parent}' are not densely numbered: {missing} is missing")]
+    ChildNumberingGap { parent: String, missing: u32 },
+
+    #[error("no child numbers left under '{parent}'")]
+    ChildNumbersExhausted { parent: String },
+
+    #[error("cannot move '{id}' under '{new_parent}', which is inside its own subtree")]
+    CyclicMove { id: String, new_parent: String },
+
+    #[error("'{id}' violates child policy: {violation}")]
+    PolicyViolation {
+        id: String,
+        violation: ChildPolicyViolation,
+    },
+
+    #[error("no hash keys left under prefix '{prefix}'")]
+    IndexFull { prefix: String },
+}
+
+/// The rule of a [`ChildPolicy`](crate::children::ChildPolicy) that an ID breaks.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChildPolicyViolation {
+    #[error("depth {depth} exceeds maximum of {max_depth}")]
+    TooDeep { depth: usize, max_depth: usize },
+
+    #[error("child number 0 is not allowed")]
+    ZeroChild,
+
+    #[error("child number {number} exceeds maximum of {max}")]
+    ChildNumberTooLarge { number: u32, max: u32 },
+
+    #[error("child segment '{segment}' has a leading zero")]
+    LeadingZero { segment: String },
+}
+
+impl TerseIdError {
+    /// Returns a two-line rendering of an `InvalidId` error that points at the problem.
+    ///
+    /// ```
+    /// let err = terseid::parse_id("bd-a7x.1x").unwrap_err();
+    /// assert_eq!(
+    ///     err.pointer().unwrap().to_string(),
+    ///     "bd-a7x.1x\n       ^^ child segment at byte 7 is not a number"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn pointer(&self) -> Option<InvalidIdPointer<'_>> {
+        match self {
+            Self::InvalidId { id, reason } => Some(InvalidIdPointer { id, reason }),
+            _ => None,
+        }
+    }
+}
+
+/// Why an ID failed to parse, with the byte span of the offending part.
+///
+/// Spans index the `id` stored in [`TerseIdError::InvalidId`], which is the
+/// input as the caller passed it, before any lowercasing.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InvalidIdReason {
+    #[error("no dash separating prefix from hash")]
+    MissingDash { span: Range<usize> },
+
+    #[error("empty prefix")]
+    EmptyPrefix { span: Range<usize> },
+
+    #[error("empty hash at byte {}", .span.start)]
+    EmptyHash { span: Range<usize> },
+
+    #[error("unexpected character '{found}' at byte {}", .span.start)]
+    InvalidChar { found: char, span: Range<usize> },
+
+    #[error("hash of 4+ characters at byte {} has no digit", .span.start)]
+    HashWithoutDigit { span: Range<usize> },
+
+    #[error("child segment at byte {} is not a number", .span.start)]
+    BadChildSegment { span: Range<usize> },
+
+    #[error("child number at byte {} does not fit in u32", .span.start)]
+    ChildNumberOverflow { span: Range<usize> },
+
+    #[error("{violation}")]
+    Policy {
+        violation: ChildPolicyViolation,
+        span: Range<usize>,
+    },
+}
+
+impl InvalidIdReason {
+    /// Returns the byte range of the offending part. Empty for missing parts.
+    #[must_use]
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::MissingDash { span }
+            | Self::EmptyPrefix { span }
+            | Self::EmptyHash { span }
+            | Self::InvalidChar { span, .. }
+            | Self::HashWithoutDigit { span }
+            | Self::BadChildSegment { span }
+            | Self::ChildNumberOverflow { span }
+            | Self::Policy { span, .. } => span.clone(),
+        }
+    }
+
+    /// Returns the offending byte range for in-place adjustment.
+    pub(crate) const fn span_mut(&mut self) -> &mut Range<usize> {
+        match self {
+            Self::MissingDash { span }
+            | Self::EmptyPrefix { span }
+            | Self::EmptyHash { span }
+            | Self::InvalidChar { span, .. }
+            | Self::HashWithoutDigit { span }
+            | Self::BadChildSegment { span }
+            | Self::ChildNumberOverflow { span }
+            | Self::Policy { span, .. } => span,
+        }
+    }
+}
+
+/// Displays an invalid ID with a caret line under the offending part.
+///
+/// Created by [`TerseIdError::pointer`].
+#[derive(Debug, Clone, Copy)]
+pub struct InvalidIdPointer<'a> {
+    id: &'a str,
+    reason: &'a InvalidIdReason,
 }
 
-pub type Result<T> = std::result::Result<T, TerseIdError>;
+impl fmt::Display for InvalidIdPointer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.reason.span();
+        let start = span.start.min(self.id.len());
+        let end = span.end.clamp(start, self.id.len());
+        // Columns count chars, so multi-byte characters take one column.
+        let column = self.id.get(..start).map_or(start, |s| s.chars().count());
+        let width = self.id.get(start..end).map_or(0, |s| s.chars().count());
+
+        writeln!(f, "{}", self.id)?;
+        write!(f, "{:column$}", "")?;
+        for _ in 0..width.max(1) {
+            f.write_char('^')?;
+        }
+        write!(f, " {}", self.reason)
+    }
+}
+
+/// Renders the candidates of an `AmbiguousId` error, e.g.
+/// `12 matches: bd-a7x1, bd-a7x2 and 10 more`.
+struct CandidateList<'a> {
+    matches: &'a [String],
+    total: usize,
+}
+
+impl<'a> CandidateList<'a> {
+    const fn new(matches: &'a [String], total: usize) -> Self {
+        Self { matches, total }
+    }
+}
+
+impl fmt::Display for CandidateList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total.max(self.matches.len());
+        write!(f, "{total} matches: ")?;
+        let rest = total - self.matches.len();
+        for (i, id) in self.matches.iter().enumerate() {
+            if i > 0 {
+                let last = i + 1 == self.matches.len() && rest == 0;
+                f.write_str(if last { " and " } else { ", " })?;
+            }
+            f.write_str(id)?;
+        }
+        if rest > 0 {
+            if self.matches.is_empty() {
+                write!(f, "{rest} not shown")?;
+            } else {
+                write!(f, " and {rest} more")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub type Result<T> = core::result::Result<T, TerseIdError>;
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
 
     #[test]
     fn test_invalid_id_display() {
         let error = TerseIdError::InvalidId {
-            id: "bad-id".to_string(),
+            id: "bad-id!".to_string(),
+            reason: InvalidIdReason::InvalidChar {
+                found: '!',
+                span: 6..7,
+            },
         };
-        assert_eq!(error.to_string(), "invalid ID format: bad-id");
+        assert_eq!(
+            error.to_string(),
+            "invalid ID format: bad-id! (unexpected character '!' at byte 6)"
+        );
+    }
+
+    #[test]
+    fn test_invalid_id_reason_display() {
+        let cases = [
+            (
+                InvalidIdReason::MissingDash { span: 0..4 },
+                "no dash separating prefix from hash",
+            ),
+            (InvalidIdReason::EmptyPrefix { span: 0..0 }, "empty prefix"),
+            (
+                InvalidIdReason::EmptyHash { span: 3..3 },
+                "empty hash at byte 3",
+            ),
+            (
+                InvalidIdReason::HashWithoutDigit { span: 3..7 },
+                "hash of 4+ characters at byte 3 has no digit",
+            ),
+            (
+                InvalidIdReason::ChildNumberOverflow { span: 7..18 },
+                "child number at byte 7 does not fit in u32",
+            ),
+            (
+                InvalidIdReason::Policy {
+                    violation: ChildPolicyViolation::ZeroChild,
+                    span: 7..8,
+                },
+                "child number 0 is not allowed",
+            ),
+        ];
+        for (reason, expected) in cases {
+            assert_eq!(reason.to_string(), expected);
+        }
+        assert_eq!(InvalidIdReason::EmptyHash { span: 3..3 }.span(), 3..3);
+    }
+
+    #[test]
+    fn test_pointer() {
+        let error = TerseIdError::InvalidId {
+            id: "bd-a7x.abc".to_string(),
+            reason: InvalidIdReason::BadChildSegment { span: 7..10 },
+        };
+        assert_eq!(
+            error.pointer().unwrap().to_string(),
+            "bd-a7x.abc\n       ^^^ child segment at byte 7 is not a number"
+        );
+        let not_invalid = TerseIdError::NotFound {
+            id: "x".to_string(),
+        };
+        assert!(not_invalid.pointer().is_none());
+    }
+
+    #[test]
+    fn test_pointer_empty_span_and_multibyte() {
+        let empty = TerseIdError::InvalidId {
+            id: "bd-".to_string(),
+            reason: InvalidIdReason::EmptyHash { span: 3..3 },
+        };
+        assert_eq!(
+            empty.pointer().unwrap().to_string(),
+            "bd-\n   ^ empty hash at byte 3"
+        );
+        let multibyte = TerseIdError::InvalidId {
+            id: "bd-é\u{212a}".to_string(),
+            reason: InvalidIdReason::InvalidChar {
+                found: '\u{212a}',
+                span: 5..8,
+            },
+        };
+        assert_eq!(
+            multibyte.pointer().unwrap().to_string(),
+            "bd-é\u{212a}\n    ^ unexpected character '\u{212a}' at byte 5"
+        );
     }
 
     #[test]
@@ -48,10 +330,49 @@ mod tests {
         let error = TerseIdError::AmbiguousId {
             partial: "usr_a".to_string(),
             matches: vec!["usr_abc123".to_string(), "usr_abd456".to_string()],
+            total: 2,
+        };
+        assert_eq!(
+            error.to_string(),
+            "ambiguous ID 'usr_a': 2 matches: usr_abc123 and usr_abd456"
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_id_display_capped() {
+        let error = TerseIdError::AmbiguousId {
+            partial: "a".to_string(),
+            matches: vec![
+                "bd-a1".to_string(),
+                "bd-a2".to_string(),
+                "bd-a3".to_string(),
+            ],
+            total: 250,
         };
         assert_eq!(
             error.to_string(),
-            "ambiguous ID 'usr_a': matches [\"usr_abc123\", \"usr_abd456\"]"
+            "ambiguous ID 'a': 250 matches: bd-a1, bd-a2, bd-a3 and 247 more"
+        );
+
+        let error = TerseIdError::AmbiguousId {
+            partial: "a".to_string(),
+            matches: vec![],
+            total: 4,
+        };
+        assert_eq!(
+            error.to_string(),
+            "ambiguous ID 'a': 4 matches: 4 not shown"
+        );
+
+        // A total smaller than the list, e.g. from old serialized errors, is ignored.
+        let error = TerseIdError::AmbiguousId {
+            partial: "a".to_string(),
+            matches: vec!["bd-a1".to_string(), "bd-a2".to_string()],
+            total: 0,
+        };
+        assert_eq!(
+            error.to_string(),
+            "ambiguous ID 'a': 2 matches: bd-a1 and bd-a2"
         );
     }
 
@@ -63,10 +384,103 @@ mod tests {
         assert_eq!(error.to_string(), "ID not found: usr_xyz789");
     }
 
+    #[test]
+    fn test_not_found_with_suggestions_display() {
+        let error = TerseIdError::NotFoundWithSuggestions {
+            id: "a7x3g9".to_string(),
+            suggestions: vec!["bd-a7x3q9".to_string()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "ID not found: a7x3g9; did you mean bd-a7x3q9?"
+        );
+
+        let error = TerseIdError::NotFoundWithSuggestions {
+            id: "a7x".to_string(),
+            suggestions: vec!["bd-a7y".to_string(), "bd-a8x".to_string()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "ID not found: a7x; did you mean bd-a7y, bd-a8x?"
+        );
+    }
+
+    #[test]
+    fn test_not_a_child_display() {
+        let error = TerseIdError::NotAChild {
+            parent: "bd-a7x".to_string(),
+            id: "bd-k2m.1".to_string(),
+        };
+        assert_eq!(error.to_string(), "'bd-k2m.1' is not a child of 'bd-a7x'");
+    }
+
+    #[test]
+    fn test_child_numbering_gap_display() {
+        let error = TerseIdError::ChildNumberingGap {
+            parent: "bd-a7x".to_string(),
+            missing: 2,
+        };
+        assert_eq!(
+            error.to_string(),
+            "children of 'bd-a7x' are not densely numbered: 2 is missing"
+        );
+    }
+
+    #[test]
+    fn test_child_numbers_exhausted_display() {
+        let error = TerseIdError::ChildNumbersExhausted {
+            parent: "bd-a7x".to_string(),
+        };
+        assert_eq!(error.to_string(), "no child numbers left under 'bd-a7x'");
+    }
+
+    #[test]
+    fn test_index_full_display() {
+        let error = TerseIdError::IndexFull {
+            prefix: "bd".to_string(),
+        };
+        assert_eq!(error.to_string(), "no hash keys left under prefix 'bd'");
+    }
+
+    #[test]
+    fn test_cyclic_move_display() {
+        let error = TerseIdError::CyclicMove {
+            id: "bd-a7x.3".to_string(),
+            new_parent: "bd-a7x.3.1".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "cannot move 'bd-a7x.3' under 'bd-a7x.3.1', which is inside its own subtree"
+        );
+    }
+
+    #[test]
+    fn test_policy_violation_display() {
+        let error = TerseIdError::PolicyViolation {
+            id: "bd-a7x.1.2.3".to_string(),
+            violation: ChildPolicyViolation::TooDeep {
+                depth: 3,
+                max_depth: 2,
+            },
+        };
+        assert_eq!(
+            error.to_string(),
+            "'bd-a7x.1.2.3' violates child policy: depth 3 exceeds maximum of 2"
+        );
+        assert_eq!(
+            ChildPolicyViolation::LeadingZero {
+                segment: "01".to_string()
+            }
+            .to_string(),
+            "child segment '01' has a leading zero"
+        );
+    }
+
     #[test]
     fn test_error_debug() {
         let error = TerseIdError::InvalidId {
             id: "test".to_string(),
+            reason: InvalidIdReason::MissingDash { span: 0..4 },
         };
         assert!(format!("{error:?}").contains("InvalidId"));
     }
@@ -84,13 +498,43 @@ mod tests {
     fn test_error_equality() {
         let error1 = TerseIdError::InvalidId {
             id: "same".to_string(),
+            reason: InvalidIdReason::MissingDash { span: 0..4 },
         };
         let error2 = TerseIdError::InvalidId {
             id: "same".to_string(),
+            reason: InvalidIdReason::MissingDash { span: 0..4 },
         };
         assert_eq!(error1, error2);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_error_serde_roundtrip() {
+        let error = TerseIdError::AmbiguousId {
+            partial: "a7".to_string(),
+            matches: vec!["bd-a7x".to_string(), "bd-a7y".to_string()],
+            total: 5,
+        };
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(
+            json,
+            r#"{"AmbiguousId":{"partial":"a7","matches":["bd-a7x","bd-a7y"],"total":5}}"#
+        );
+        let back: TerseIdError = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, error);
+
+        let invalid = TerseIdError::InvalidId {
+            id: "bd-a7x!".to_string(),
+            reason: InvalidIdReason::InvalidChar {
+                found: '!',
+                span: 6..7,
+            },
+        };
+        let json = serde_json::to_string(&invalid).unwrap();
+        let back: TerseIdError = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, invalid);
+    }
+
     #[test]
     fn test_result_type_ok() {
         let result: Result<i32> = Ok(42);
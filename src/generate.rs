@@ -1,4 +1,6 @@
 use crate::config::IdConfig;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// ID generator with adaptive length and collision avoidance.
 pub struct IdGenerator {
@@ -36,9 +38,9 @@ impl IdGenerator {
 
         for length in self.config.min_hash_length..=self.config.max_hash_length {
             // length is bounded by max_hash_length (default 8), safe to cast
-            let d = 36_f64.powi(length as i32);
-            let exponent = -((n.powi(2)) / (2.0 * d));
-            let p_collision = 1.0 - exponent.exp();
+            let d = powi(36_f64, length as i32);
+            let exponent = -((powi(n, 2)) / (2.0 * d));
+            let p_collision = 1.0 - exp(exponent);
 
             if p_collision < self.config.max_collision_prob {
                 return length;
@@ -123,10 +125,33 @@ impl IdGenerator {
     }
 }
 
+/// `base^n`, through `libm` when `std` is unavailable.
+#[cfg(feature = "std")]
+fn powi(base: f64, n: i32) -> f64 {
+    base.powi(n)
+}
+
+#[cfg(not(feature = "std"))]
+fn powi(base: f64, n: i32) -> f64 {
+    libm::pow(base, f64::from(n))
+}
+
+/// `e^x`, through `libm` when `std` is unavailable.
+#[cfg(feature = "std")]
+fn exp(x: f64) -> f64 {
+    x.exp()
+}
+
+#[cfg(not(feature = "std"))]
+fn exp(x: f64) -> f64 {
+    libm::exp(x)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
 
     #[test]
     fn test_new_creates_generator() {
@@ -0,0 +1,478 @@
+//! An in-memory index of IDs for fast resolution.
+//!
+//! [`find_matching_ids`](crate::resolve::find_matching_ids) re-parses every ID
+//! on each call. [`IdIndex`] parses each ID once, groups IDs by prefix, and
+//! keeps a suffix trie over each group's hashes, so exact, hash-prefix and
+//! substring lookups only touch the IDs they return.
+
+use crate::error::{Result, TerseIdError};
+use crate::parse::{ParsedId, parse_id};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A node of a suffix trie over the hashes of a [`PrefixGroup`], which are
+/// referred to by their interned key.
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: BTreeMap<char, Self>,
+    /// Keys of hashes that contain the path to this node.
+    containing: BTreeSet<u32>,
+    /// Keys of hashes that start with the path to this node.
+    starting: BTreeSet<u32>,
+}
+
+impl TrieNode {
+    fn insert(&mut self, hash: &str, key: u32) {
+        self.containing.insert(key);
+        self.starting.insert(key);
+        for (start, _) in hash.char_indices() {
+            let mut node = &mut *self;
+            for c in hash[start..].chars() {
+                node = node.children.entry(c).or_default();
+                node.containing.insert(key);
+                if start == 0 {
+                    node.starting.insert(key);
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, hash: &str, key: u32) {
+        self.containing.remove(&key);
+        self.starting.remove(&key);
+        for (start, _) in hash.char_indices() {
+            Self::remove_path(self, &hash[start..], key);
+        }
+    }
+
+    /// Removes `key` along `path`, dropping nodes that no hash reaches anymore.
+    fn remove_path(node: &mut Self, path: &str, key: u32) {
+        let mut chars = path.chars();
+        let Some(c) = chars.next() else {
+            return;
+        };
+        let Some(child) = node.children.get_mut(&c) else {
+            return;
+        };
+        child.containing.remove(&key);
+        child.starting.remove(&key);
+        Self::remove_path(child, chars.as_str(), key);
+        if child.containing.is_empty() {
+            node.children.remove(&c);
+        }
+    }
+
+    fn find(&self, path: &str) -> Option<&Self> {
+        path.chars().try_fold(self, |node, c| node.children.get(&c))
+    }
+}
+
+/// The IDs with one hash, and the key the trie knows the hash by.
+#[derive(Debug, Clone)]
+struct HashEntry {
+    key: u32,
+    /// A root and/or its descendants.
+    ids: BTreeSet<ParsedId>,
+}
+
+/// The IDs sharing one prefix.
+#[derive(Debug, Clone, Default)]
+struct PrefixGroup {
+    by_hash: BTreeMap<String, HashEntry>,
+    /// Each hash by key, interned once so trie nodes only store keys. Keys of
+    /// removed hashes are `None` until reused.
+    hashes: Vec<Option<String>>,
+    free_keys: Vec<u32>,
+    suffixes: TrieNode,
+}
+
+impl PrefixGroup {
+    fn insert(&mut self, parsed: ParsedId) -> Result<bool> {
+        if let Some(entry) = self.by_hash.get_mut(&parsed.hash) {
+            return Ok(entry.ids.insert(parsed));
+        }
+        let Some(key) = self.intern(&parsed.hash) else {
+            return Err(TerseIdError::IndexFull {
+                prefix: parsed.prefix,
+            });
+        };
+        self.suffixes.insert(&parsed.hash, key);
+        let hash = parsed.hash.clone();
+        let ids = BTreeSet::from([parsed]);
+        self.by_hash.insert(hash, HashEntry { key, ids });
+        Ok(true)
+    }
+
+    fn remove(&mut self, parsed: &ParsedId) -> bool {
+        let Some(entry) = self.by_hash.get_mut(&parsed.hash) else {
+            return false;
+        };
+        if !entry.ids.remove(parsed) {
+            return false;
+        }
+        if entry.ids.is_empty() {
+            let key = entry.key;
+            self.by_hash.remove(&parsed.hash);
+            self.suffixes.remove(&parsed.hash, key);
+            if let Some(slot) = self.hashes.get_mut(key as usize) {
+                *slot = None;
+            }
+            self.free_keys.push(key);
+        }
+        true
+    }
+
+    /// Stores `hash` under a free key, or returns `None` if all `u32` keys are taken.
+    fn intern(&mut self, hash: &str) -> Option<u32> {
+        if let Some(key) = self.free_keys.pop() {
+            if let Some(slot) = self.hashes.get_mut(key as usize) {
+                *slot = Some(String::from(hash));
+            }
+            return Some(key);
+        }
+        let key = u32::try_from(self.hashes.len()).ok()?;
+        self.hashes.push(Some(String::from(hash)));
+        Some(key)
+    }
+
+    /// The IDs whose hash key is in `keys`, in natural order.
+    fn ids_for<'a>(&'a self, keys: &BTreeSet<u32>) -> impl Iterator<Item = &'a ParsedId> + 'a {
+        let mut hashes: Vec<&str> = keys
+            .iter()
+            .filter_map(|&key| self.hashes.get(key as usize)?.as_deref())
+            .collect();
+        hashes.sort_unstable();
+        hashes
+            .into_iter()
+            .filter_map(|hash| self.by_hash.get(hash))
+            .flat_map(|entry| &entry.ids)
+    }
+}
+
+/// A set of IDs indexed by prefix and hash.
+///
+/// Lookups are case-insensitive and results are normalized and returned in
+/// natural order. [`IdResolver::resolve_indexed`](crate::resolve::IdResolver::resolve_indexed)
+/// resolves input against an index directly.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::IdIndex;
+///
+/// let mut index: IdIndex = ["bd-a7x3q9", "bd-a7x3q9.1", "bd-k2m4p1"].into_iter().collect();
+/// assert!(index.contains("BD-A7X3Q9"));
+/// assert_eq!(index.find_by_hash_prefix("a7"), ["bd-a7x3q9", "bd-a7x3q9.1"]);
+/// assert_eq!(index.find_matching("m4"), ["bd-k2m4p1"]);
+///
+/// index.remove("bd-a7x3q9.1");
+/// assert_eq!(index.find_by_hash_prefix("a7"), ["bd-a7x3q9"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IdIndex {
+    groups: BTreeMap<String, PrefixGroup>,
+    len: usize,
+}
+
+impl IdIndex {
+    /// Creates an empty index.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds an index from a list of IDs.
+    ///
+    /// IDs that fail to parse, or that [`insert`](Self::insert) rejects, are
+    /// skipped. Duplicates (including case variants) are merged.
+    pub fn from_ids<I>(ids: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut index = Self::new();
+        for id in ids {
+            let _ = index.insert(id.as_ref());
+        }
+        index
+    }
+
+    /// Adds an ID. Returns true if it was not already present.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidId` if `id` cannot be parsed.
+    /// Returns `IndexFull` if its prefix already has 2^32 distinct hashes.
+    pub fn insert(&mut self, id: &str) -> Result<bool> {
+        let parsed = parse_id(id)?;
+        let added = self
+            .groups
+            .entry(parsed.prefix.clone())
+            .or_default()
+            .insert(parsed)?;
+        if added {
+            self.len += 1;
+        }
+        Ok(added)
+    }
+
+    /// Removes an ID. Returns true if it was present.
+    ///
+    /// Removing a root leaves its descendants in the index.
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Ok(parsed) = parse_id(id) else {
+            return false;
+        };
+        let Some(group) = self.groups.get_mut(&parsed.prefix) else {
+            return false;
+        };
+        if !group.remove(&parsed) {
+            return false;
+        }
+        if group.by_hash.is_empty() {
+            self.groups.remove(&parsed.prefix);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Returns the number of IDs in the index.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the index has no IDs.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if `id` is in the index.
+    #[must_use]
+    pub fn contains(&self, id: &str) -> bool {
+        parse_id(id).is_ok_and(|parsed| {
+            self.groups
+                .get(&parsed.prefix)
+                .and_then(|group| group.by_hash.get(&parsed.hash))
+                .is_some_and(|entry| entry.ids.contains(&parsed))
+        })
+    }
+
+    /// Iterates over the prefixes that have at least one ID, in order.
+    pub fn prefixes(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    /// Iterates over every ID in natural order.
+    pub fn iter(&self) -> impl Iterator<Item = &ParsedId> {
+        self.groups
+            .values()
+            .flat_map(|group| group.by_hash.values().flat_map(|entry| &entry.ids))
+    }
+
+    /// Finds IDs whose hash starts with `hash_prefix`, like
+    /// [`find_ids_by_hash_prefix`](crate::resolve::find_ids_by_hash_prefix).
+    #[must_use]
+    pub fn find_by_hash_prefix(&self, hash_prefix: &str) -> Vec<String> {
+        self.query(hash_prefix, |node| &node.starting)
+    }
+
+    /// Finds IDs whose hash contains `hash_substring`, like
+    /// [`find_matching_ids`](crate::resolve::find_matching_ids).
+    ///
+    /// An empty substring matches every ID.
+    #[must_use]
+    pub fn find_matching(&self, hash_substring: &str) -> Vec<String> {
+        self.query(hash_substring, |node| &node.containing)
+    }
+
+    fn query(&self, needle: &str, keys: fn(&TrieNode) -> &BTreeSet<u32>) -> Vec<String> {
+        let needle = needle.to_lowercase();
+        self.groups
+            .values()
+            .filter_map(|group| {
+                group
+                    .suffixes
+                    .find(&needle)
+                    .map(|node| group.ids_for(keys(node)))
+            })
+            .flatten()
+            .map(ParsedId::to_id_string)
+            .collect()
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for IdIndex {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self::from_ids(iter)
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for IdIndex {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for id in iter {
+            let _ = self.insert(id.as_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::resolve::{find_ids_by_hash_prefix, find_matching_ids};
+    use alloc::string::ToString;
+
+    fn sample() -> IdIndex {
+        IdIndex::from_ids([
+            "bd-a7x3q9",
+            "bd-a7x3q9.2",
+            "bd-a7x3q9.10",
+            "bd-k2m4p1",
+            "bd-qa7x1",
+            "tk-a7y2k1",
+        ])
+    }
+
+    #[test]
+    fn test_empty_index() {
+        let index = IdIndex::new();
+        assert!(index.is_empty());
+        assert!(!index.contains("bd-a7x"));
+        assert!(index.find_matching("").is_empty());
+        assert_eq!(index.prefixes().count(), 0);
+    }
+
+    #[test]
+    fn test_contains_and_len() {
+        let index = sample();
+        assert_eq!(index.len(), 6);
+        assert!(index.contains("bd-a7x3q9"));
+        assert!(index.contains("BD-A7X3Q9.2"));
+        assert!(!index.contains("bd-a7x3q9.3"));
+        assert!(!index.contains("bd-a7x3"));
+        assert!(!index.contains("not an id"));
+    }
+
+    #[test]
+    fn test_find_by_hash_prefix() {
+        let index = sample();
+        assert_eq!(
+            index.find_by_hash_prefix("A7"),
+            vec!["bd-a7x3q9", "bd-a7x3q9.2", "bd-a7x3q9.10", "tk-a7y2k1"]
+        );
+        assert!(index.find_by_hash_prefix("x3").is_empty());
+    }
+
+    #[test]
+    fn test_find_matching() {
+        let index = sample();
+        assert_eq!(
+            index.find_matching("a7x"),
+            vec!["bd-a7x3q9", "bd-a7x3q9.2", "bd-a7x3q9.10", "bd-qa7x1"]
+        );
+        assert_eq!(index.find_matching("2k"), vec!["tk-a7y2k1"]);
+        assert_eq!(index.find_matching("").len(), 6);
+        assert!(index.find_matching("zz").is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_duplicates() {
+        let mut index = IdIndex::new();
+        assert!(index.insert("bd-a7x").unwrap());
+        assert!(!index.insert("BD-A7X").unwrap());
+        assert!(index.insert("bd-a7x.1").unwrap());
+        assert!(index.insert("nope").is_err());
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_prunes() {
+        let mut index = sample();
+        assert!(index.remove("tk-a7y2k1"));
+        assert!(!index.remove("tk-a7y2k1"));
+        assert!(!index.remove("not an id"));
+        assert_eq!(index.prefixes().collect::<Vec<_>>(), vec!["bd"]);
+        assert!(index.find_matching("2k").is_empty());
+
+        // The hash stays searchable while any ID with it remains.
+        assert!(index.remove("bd-a7x3q9"));
+        assert_eq!(index.find_matching("x3q").len(), 2);
+        assert!(index.remove("bd-a7x3q9.2"));
+        assert!(index.remove("bd-a7x3q9.10"));
+        assert!(index.find_matching("x3q").is_empty());
+        assert!(!index.groups["bd"].suffixes.children.contains_key(&'3'));
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_hashes_interned_once_and_keys_reused() {
+        let mut index = IdIndex::from_ids(["bd-a7x3q9", "bd-a7x3q9.1", "bd-k2m4p1"]);
+        let group = &index.groups["bd"];
+        assert_eq!(group.hashes.len(), 2);
+        let key = group.by_hash["a7x3q9"].key;
+        assert!(
+            group
+                .suffixes
+                .find("x3q")
+                .unwrap()
+                .containing
+                .contains(&key)
+        );
+
+        assert!(index.remove("bd-k2m4p1"));
+        assert!(index.insert("bd-r9p2k4").unwrap());
+        let group = &index.groups["bd"];
+        assert_eq!(group.hashes.len(), 2);
+        assert_eq!(index.find_matching("p2"), ["bd-r9p2k4"]);
+        assert!(index.find_matching("m4").is_empty());
+    }
+
+    #[test]
+    fn test_iter_and_extend() {
+        let mut index = IdIndex::new();
+        index.extend(["tk-b1", "bd-a7x.10", "bd-a7x.2", "bd-a7x"]);
+        let ids: Vec<String> = index.iter().map(ToString::to_string).collect();
+        assert_eq!(ids, vec!["bd-a7x", "bd-a7x.2", "bd-a7x.10", "tk-b1"]);
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn prop_matches_linear_scan(
+                ids in prop::collection::vec("[a-c]{1,2}-[0-9a-e]{2,5}(\\.[1-3]){0,2}", 0..30),
+                removed in prop::collection::vec(0usize..30, 0..10),
+                needle in "[0-9a-e]{0,3}",
+            ) {
+                let mut index = IdIndex::from_ids(&ids);
+                let mut remaining = ids.clone();
+                for i in removed {
+                    if let Some(id) = ids.get(i) {
+                        index.remove(id);
+                        remaining.retain(|r| r != id);
+                    }
+                }
+                let sorted = |found: Vec<String>| {
+                    let mut parsed: Vec<ParsedId> =
+                        found.into_iter().map(|id| parse_id(&id).unwrap()).collect();
+                    parsed.sort();
+                    parsed.dedup();
+                    parsed.into_iter().map(|id| id.to_id_string()).collect::<Vec<_>>()
+                };
+                prop_assert_eq!(
+                    index.find_matching(&needle),
+                    sorted(find_matching_ids(&remaining, &needle))
+                );
+                prop_assert_eq!(
+                    index.find_by_hash_prefix(&needle),
+                    sorted(find_ids_by_hash_prefix(&remaining, &needle))
+                );
+            }
+        }
+    }
+}
@@ -1,19 +1,59 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 
+#[macro_use]
+extern crate alloc;
+
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
 pub mod children;
 pub mod config;
 pub mod error;
 pub mod generate;
 pub mod hash;
+pub mod index;
+pub mod normalize;
 pub mod parse;
+pub mod remap;
 pub mod resolve;
+pub mod stage;
+pub mod text;
+pub mod tree;
+pub mod typed;
 
-pub use children::{child_id, id_depth, is_child_id};
+pub use children::{
+    ChildAllocator, ChildNumbering, ChildPolicy, child_id, id_depth, is_child_id, next_child_id,
+    try_child_id,
+};
 pub use config::IdConfig;
-pub use error::{Result, TerseIdError};
+pub use error::{ChildPolicyViolation, InvalidIdPointer, InvalidIdReason, Result, TerseIdError};
 pub use generate::IdGenerator;
-pub use parse::{ParsedId, is_valid_id_format, normalize_id, parse_id, validate_prefix};
-pub use resolve::{IdResolver, MatchType, ResolvedId, ResolverConfig, find_matching_ids};
+pub use index::IdIndex;
+pub use normalize::{InputNormalizer, Normalization};
+pub use parse::{
+    AncestorId, Ancestors, ChildSegments, ParseMode, ParseOptions, ParsedId, ParsedIdRef,
+    RelativePath, compare_ids, is_canonical, is_valid_id_format, normalize_id, parse_id,
+    parse_id_with, parse_id_with_policy, validate_prefix,
+};
+pub use remap::{Remap, reparent};
+pub use resolve::{
+    BatchResolution, FuzzyConfig, IdResolver, IdStore, MatchType, ResolvedId, ResolverConfig,
+    find_ids_by_hash_prefix, find_matching_ids, find_similar_ids,
+};
+pub use stage::{ResolutionStage, StageContext};
+pub use text::{
+    IdMatch, ScanOptions, find_ids_in_text, find_ids_in_text_with, rewrite_ids, rewrite_words,
+};
+pub use tree::IdTree;
+
+use alloc::string::String;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::string::String;
+    pub use alloc::vec::Vec;
+}
 
 /// Compute a base36 hash of the input, truncated or zero-padded to `length` characters.
 pub fn hash(input: impl AsRef<[u8]>, length: usize) -> String {
@@ -0,0 +1,334 @@
+//! Cleaning up pasted or mistyped resolver input.
+//!
+//! [`InputNormalizer`] turns things like `#BD-A7X3,` or
+//! `https://tracker.example/issues/bd-a7x3` into `bd-a7x3` before
+//! [`IdResolver`](crate::resolve::IdResolver) looks them up, and records each
+//! step as a [`Normalization`].
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Characters stripped from the start of the input.
+const LEADING_NOISE: &[char] = &['#', '"', '\'', '`', '(', '[', '{', '<'];
+
+/// Characters stripped from the end of the input.
+const TRAILING_NOISE: &[char] = &[
+    ',', '.', ';', ':', '!', '?', '"', '\'', '`', ')', ']', '}', '>',
+];
+
+/// One change an [`InputNormalizer`] made to the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Normalization {
+    /// Leading or trailing whitespace was removed.
+    Trimmed,
+    /// Uppercase characters were lowercased.
+    Lowercased,
+    /// Characters such as `#`, quotes or brackets were removed from the start.
+    StrippedLeading { removed: String },
+    /// Punctuation, quotes or brackets were removed from the end.
+    StrippedTrailing { removed: String },
+    /// The input was a URL and only its last path segment was kept.
+    UrlPathSegment { url: String },
+    /// Look-alike characters in the hash were replaced (`o` to `0`, `i`/`l` to `1`).
+    Lookalikes { from: String, to: String },
+}
+
+/// Configurable cleanup of resolver input.
+///
+/// Set it as [`ResolverConfig::normalizer`](crate::resolve::ResolverConfig::normalizer).
+///
+/// Trimming and lowercasing always happen. The other steps can be switched off.
+/// Look-alike mapping is off by default because `o`, `i` and `l` are valid
+/// base36 digits; when enabled, the resolver only tries the mapped form after
+/// the input as typed finds nothing.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::{InputNormalizer, Normalization};
+///
+/// let (cleaned, steps) = InputNormalizer::new().normalize("  (#BD-A7X3),");
+/// assert_eq!(cleaned, "bd-a7x3");
+/// assert_eq!(steps.len(), 4);
+///
+/// let (cleaned, _) = InputNormalizer::new().normalize("https://example.com/issues/bd-a7x3?tab=1");
+/// assert_eq!(cleaned, "bd-a7x3");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputNormalizer {
+    /// Strip leading `#`, quotes and brackets, and trailing punctuation.
+    pub strip_noise: bool,
+    /// Reduce URLs to their last path segment.
+    pub url_path_segment: bool,
+    /// Try Crockford-style look-alikes (`o` to `0`, `i`/`l` to `1`) as a fallback.
+    pub lookalikes: bool,
+}
+
+impl InputNormalizer {
+    /// Creates a normalizer that strips noise and URLs but leaves look-alikes alone.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            strip_noise: true,
+            url_path_segment: true,
+            lookalikes: false,
+        }
+    }
+
+    /// Creates a normalizer that only trims and lowercases.
+    #[must_use]
+    pub const fn minimal() -> Self {
+        Self {
+            strip_noise: false,
+            url_path_segment: false,
+            lookalikes: false,
+        }
+    }
+
+    /// Sets whether leading `#`, quotes and brackets and trailing punctuation are stripped.
+    #[must_use]
+    pub const fn strip_noise(mut self, enabled: bool) -> Self {
+        self.strip_noise = enabled;
+        self
+    }
+
+    /// Sets whether URLs are reduced to their last path segment.
+    #[must_use]
+    pub const fn url_path_segment(mut self, enabled: bool) -> Self {
+        self.url_path_segment = enabled;
+        self
+    }
+
+    /// Sets whether look-alike characters in the hash are tried as a fallback.
+    #[must_use]
+    pub const fn lookalikes(mut self, enabled: bool) -> Self {
+        self.lookalikes = enabled;
+        self
+    }
+
+    /// Cleans up `input`, returning the result and the steps that changed it.
+    ///
+    /// Look-alike mapping is not applied here; see [`map_lookalikes`](Self::map_lookalikes).
+    #[must_use]
+    pub fn normalize(&self, input: &str) -> (String, Vec<Normalization>) {
+        let mut steps = Vec::new();
+
+        let trimmed = input.trim();
+        if trimmed.len() != input.len() {
+            steps.push(Normalization::Trimmed);
+        }
+        let mut current = trimmed.to_lowercase();
+        if current != trimmed {
+            steps.push(Normalization::Lowercased);
+        }
+
+        if self.strip_noise {
+            current = strip(&current, &mut steps);
+        }
+        if self.url_path_segment
+            && let Some(segment) = url_path_segment(&current)
+        {
+            let segment = segment.to_string();
+            steps.push(Normalization::UrlPathSegment { url: current });
+            current = segment;
+            if self.strip_noise {
+                current = strip(&current, &mut steps);
+            }
+        }
+
+        (current, steps)
+    }
+
+    /// Maps look-alike characters in the hash part of `normalized`.
+    ///
+    /// Returns `None` if look-alikes are disabled or nothing would change.
+    /// Only the hash is touched: the part after the last dash, up to the
+    /// first dot. Prefixes keep their letters and child paths are left as typed.
+    #[must_use]
+    pub fn map_lookalikes(&self, normalized: &str) -> Option<String> {
+        if !self.lookalikes {
+            return None;
+        }
+        let hash_start = normalized.rfind('-').map_or(0, |dash| dash + 1);
+        let hash_end = normalized[hash_start..]
+            .find('.')
+            .map_or(normalized.len(), |dot| hash_start + dot);
+        let hash = &normalized[hash_start..hash_end];
+        if !hash.contains(['o', 'i', 'l']) {
+            return None;
+        }
+        let mapped: String = hash
+            .chars()
+            .map(|c| match c {
+                'o' => '0',
+                'i' | 'l' => '1',
+                other => other,
+            })
+            .collect();
+        Some(format!(
+            "{}{mapped}{}",
+            &normalized[..hash_start],
+            &normalized[hash_end..]
+        ))
+    }
+}
+
+impl Default for InputNormalizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn strip(current: &str, steps: &mut Vec<Normalization>) -> String {
+    let without_leading = current.trim_start_matches(LEADING_NOISE);
+    let leading = current.len() - without_leading.len();
+    let stripped = without_leading.trim_end_matches(TRAILING_NOISE);
+    let trailing_start = leading + stripped.len();
+
+    if leading > 0 {
+        steps.push(Normalization::StrippedLeading {
+            removed: current[..leading].to_string(),
+        });
+    }
+    if trailing_start < current.len() {
+        steps.push(Normalization::StrippedTrailing {
+            removed: current[trailing_start..].to_string(),
+        });
+    }
+    current[leading..trailing_start].to_string()
+}
+
+/// The last non-empty path segment of a URL, without query or fragment.
+fn url_path_segment(input: &str) -> Option<&str> {
+    let (_, rest) = input.split_once("://")?;
+    let path = rest.split(['?', '#']).next().unwrap_or(rest);
+    let (_, path) = path.split_once('/')?;
+    path.rsplit('/').find(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimal_only_trims_and_lowercases() {
+        let (cleaned, steps) = InputNormalizer::minimal().normalize("  #BD-A7X,");
+        assert_eq!(cleaned, "#bd-a7x,");
+        assert_eq!(
+            steps,
+            vec![Normalization::Trimmed, Normalization::Lowercased]
+        );
+    }
+
+    #[test]
+    fn test_no_changes_no_steps() {
+        let (cleaned, steps) = InputNormalizer::new().normalize("bd-a7x");
+        assert_eq!(cleaned, "bd-a7x");
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_strip_noise() {
+        let (cleaned, steps) = InputNormalizer::new().normalize("(#bd-a7x3),");
+        assert_eq!(cleaned, "bd-a7x3");
+        assert_eq!(
+            steps,
+            vec![
+                Normalization::StrippedLeading {
+                    removed: "(#".to_string()
+                },
+                Normalization::StrippedTrailing {
+                    removed: "),".to_string()
+                },
+            ]
+        );
+        assert_eq!(InputNormalizer::new().normalize("'a7x'").0, "a7x");
+        assert_eq!(
+            InputNormalizer::new().normalize("`bd-a7x.1`.").0,
+            "bd-a7x.1"
+        );
+    }
+
+    #[test]
+    fn test_url_path_segment() {
+        let normalizer = InputNormalizer::new();
+        for url in [
+            "https://example.com/issues/bd-a7x3",
+            "https://example.com/issues/bd-a7x3/",
+            "https://example.com/issues/BD-A7X3?tab=comments",
+            "https://example.com/issues/bd-a7x3#note-2",
+            "<https://example.com/issues/bd-a7x3>",
+        ] {
+            assert_eq!(normalizer.normalize(url).0, "bd-a7x3", "{url}");
+        }
+        let (_, steps) = normalizer.normalize("https://example.com/bd-a7x3");
+        assert_eq!(
+            steps,
+            vec![Normalization::UrlPathSegment {
+                url: "https://example.com/bd-a7x3".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_url_without_path_kept() {
+        let normalizer = InputNormalizer::new();
+        assert_eq!(
+            normalizer.normalize("https://example.com").0,
+            "https://example.com"
+        );
+        assert_eq!(
+            InputNormalizer::new()
+                .url_path_segment(false)
+                .normalize("https://example.com/bd-a7x")
+                .0,
+            "https://example.com/bd-a7x"
+        );
+    }
+
+    #[test]
+    fn test_url_segment_then_strip() {
+        let (cleaned, steps) = InputNormalizer::new().normalize("https://x.io/a/bd-a7x3.");
+        // The trailing dot is stripped before the URL is split.
+        assert_eq!(cleaned, "bd-a7x3");
+        assert_eq!(steps.len(), 2);
+    }
+
+    #[test]
+    fn test_lookalikes() {
+        let normalizer = InputNormalizer::new().lookalikes(true);
+        assert_eq!(
+            normalizer.map_lookalikes("bold-a7xo"),
+            Some("bold-a7x0".to_string())
+        );
+        assert_eq!(normalizer.map_lookalikes("l0i"), Some("101".to_string()));
+        assert_eq!(normalizer.map_lookalikes("bd-a7x"), None);
+        assert_eq!(InputNormalizer::new().map_lookalikes("bd-o0o"), None);
+    }
+
+    #[test]
+    fn test_lookalikes_leave_child_path_alone() {
+        let normalizer = InputNormalizer::new().lookalikes(true);
+        assert_eq!(
+            normalizer.map_lookalikes("bd-a7xo.1o"),
+            Some("bd-a7x0.1o".to_string())
+        );
+        assert_eq!(normalizer.map_lookalikes("bd-a7x.lo"), None);
+        assert_eq!(
+            normalizer.map_lookalikes("a7xl.2"),
+            Some("a7x1.2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_default_and_builders() {
+        assert_eq!(InputNormalizer::default(), InputNormalizer::new());
+        let normalizer = InputNormalizer::minimal()
+            .strip_noise(true)
+            .url_path_segment(true);
+        assert_eq!(normalizer, InputNormalizer::new());
+    }
+}
@@ -1,10 +1,21 @@
-use crate::error::{Result, TerseIdError};
-use std::fmt;
+use crate::children::ChildPolicy;
+use crate::error::{ChildPolicyViolation, InvalidIdReason, Result, TerseIdError};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::num::IntErrorKind;
+use core::ops::Range;
+use core::str::FromStr;
 
 /// Parsed representation of a terseid ID.
 ///
 /// Format: `<prefix>-<hash>[.<child>.<path>]`
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Ordering compares prefix, then hash, then child path numerically, so a
+/// parent sorts directly before its descendants and `.2` sorts before `.10`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ParsedId {
     pub prefix: String,
     pub hash: String,
@@ -81,6 +92,196 @@ impl ParsedId {
             .zip(parent.child_path.iter())
             .all(|(a, b)| a == b)
     }
+
+    /// Returns a borrowed view of this ID.
+    #[must_use]
+    pub fn as_ancestor(&self) -> AncestorId<'_> {
+        self.truncated(self.depth())
+    }
+
+    /// Iterates over the ancestors of this ID, nearest first.
+    ///
+    /// For `bd-a7x.1.3` this yields `bd-a7x.1` then `bd-a7x`. Use `.rev()` for
+    /// root-first order (e.g. breadcrumbs). Each item borrows from `self`.
+    #[must_use]
+    pub const fn ancestors(&self) -> Ancestors<'_> {
+        Ancestors {
+            id: self,
+            depths: 0..self.depth(),
+        }
+    }
+
+    /// Returns the deepest ID that is `a`, `b`, or an ancestor of both.
+    ///
+    /// Returns `None` if the IDs are in different trees (different prefix or hash).
+    #[must_use]
+    pub fn common_ancestor<'a>(a: &'a Self, b: &Self) -> Option<AncestorId<'a>> {
+        if a.prefix != b.prefix || a.hash != b.hash {
+            return None;
+        }
+        let shared = a
+            .child_path
+            .iter()
+            .zip(&b.child_path)
+            .take_while(|(x, y)| x == y)
+            .count();
+        Some(a.truncated(shared))
+    }
+
+    /// Describes how to get from `from` to `to` within the same tree.
+    ///
+    /// The path climbs `up` levels from `from` to their common ancestor, then
+    /// descends through the child numbers in `down`. Returns `None` if the IDs
+    /// are in different trees.
+    #[must_use]
+    pub fn relative_path<'a>(from: &Self, to: &'a Self) -> Option<RelativePath<'a>> {
+        let common = Self::common_ancestor(to, from)?.depth();
+        Some(RelativePath {
+            up: from.depth() - common,
+            down: &to.child_path[common..],
+        })
+    }
+
+    fn truncated(&self, depth: usize) -> AncestorId<'_> {
+        AncestorId {
+            prefix: &self.prefix,
+            hash: &self.hash,
+            child_path: &self.child_path[..depth],
+        }
+    }
+}
+
+/// Borrowed view of an ID at some level of a [`ParsedId`]'s hierarchy.
+///
+/// Yielded by [`ParsedId::ancestors`] and returned by [`ParsedId::common_ancestor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AncestorId<'a> {
+    pub prefix: &'a str,
+    pub hash: &'a str,
+    pub child_path: &'a [u32],
+}
+
+impl AncestorId<'_> {
+    /// Returns true if this ID has no child path segments.
+    #[must_use]
+    pub const fn is_root(&self) -> bool {
+        self.child_path.is_empty()
+    }
+
+    /// Returns the depth of this ID (number of child path segments).
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.child_path.len()
+    }
+
+    /// Converts this view into an owned `ParsedId`.
+    #[must_use]
+    pub fn to_owned(&self) -> ParsedId {
+        ParsedId {
+            prefix: self.prefix.to_string(),
+            hash: self.hash.to_string(),
+            child_path: self.child_path.to_vec(),
+        }
+    }
+
+    /// Formats this view as a complete ID string.
+    #[must_use]
+    pub fn to_id_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for AncestorId<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.prefix, self.hash)?;
+        for segment in self.child_path {
+            write!(f, ".{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq<ParsedId> for AncestorId<'_> {
+    fn eq(&self, other: &ParsedId) -> bool {
+        self.prefix == other.prefix
+            && self.hash == other.hash
+            && self.child_path == other.child_path
+    }
+}
+
+/// Iterator over the ancestors of a [`ParsedId`], nearest first.
+#[derive(Debug, Clone)]
+pub struct Ancestors<'a> {
+    id: &'a ParsedId,
+    depths: Range<usize>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = AncestorId<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.depths
+            .next_back()
+            .map(|depth| self.id.truncated(depth))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.depths.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Ancestors<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.depths.next().map(|depth| self.id.truncated(depth))
+    }
+}
+
+impl ExactSizeIterator for Ancestors<'_> {}
+
+impl FusedIterator for Ancestors<'_> {}
+
+/// How two IDs in the same tree relate. See [`ParsedId::relative_path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelativePath<'a> {
+    /// Levels to climb from the starting ID to the common ancestor.
+    pub up: usize,
+    /// Child numbers to descend from the common ancestor to the target.
+    pub down: &'a [u32],
+}
+
+impl RelativePath<'_> {
+    /// Returns true if both IDs are the same.
+    #[must_use]
+    pub const fn is_same(&self) -> bool {
+        self.up == 0 && self.down.is_empty()
+    }
+}
+
+/// Formats as `../../3/1`: one `..` per level up, then the child numbers down.
+/// The empty path formats as `.`.
+impl fmt::Display for RelativePath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_same() {
+            return f.write_str(".");
+        }
+        let ups = core::iter::repeat_n("..", self.up);
+        let mut first = true;
+        for part in ups {
+            if !first {
+                f.write_str("/")?;
+            }
+            f.write_str(part)?;
+            first = false;
+        }
+        for segment in self.down {
+            if !first {
+                f.write_str("/")?;
+            }
+            write!(f, "{segment}")?;
+            first = false;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for ParsedId {
@@ -89,11 +290,222 @@ impl fmt::Display for ParsedId {
     }
 }
 
+impl FromStr for ParsedId {
+    type Err = TerseIdError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_id(s)
+    }
+}
+
+/// Serializes as the canonical ID string (e.g. `"bd-a7x.1"`).
+#[cfg(feature = "serde")]
+impl serde::Serialize for ParsedId {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserializes from an ID string, validating it through `parse_id`.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ParsedId {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ParsedIdVisitor;
+
+        impl serde::de::Visitor<'_> for ParsedIdVisitor {
+            type Value = ParsedId;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a terseid ID string")
+            }
+
+            fn visit_str<E>(self, value: &str) -> core::result::Result<ParsedId, E>
+            where
+                E: serde::de::Error,
+            {
+                parse_id(value).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(ParsedIdVisitor)
+    }
+}
+
 /// Checks if a character is valid in base36.
 const fn is_base36(c: char) -> bool {
     c.is_ascii_alphanumeric()
 }
 
+/// Borrowed, zero-allocation view of a terseid ID.
+///
+/// Produced by [`ParsedIdRef::parse`], which validates the same grammar as
+/// [`parse_id`] without lowercasing or copying the input. The prefix and hash
+/// are slices of the input exactly as written; use [`ParsedIdRef::to_owned`]
+/// to get a normalized [`ParsedId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedIdRef<'a> {
+    pub prefix: &'a str,
+    pub hash: &'a str,
+    /// Child path text after the hash, without the leading dot (e.g. `"1.3"`).
+    children: &'a str,
+}
+
+impl<'a> ParsedIdRef<'a> {
+    /// Parses and validates an ID without allocating.
+    ///
+    /// Accepts the same IDs as [`parse_id`], except that the hash must be
+    /// ASCII as written: `parse_id` lowercases with Unicode rules first, so it
+    /// also accepts characters like the Kelvin sign (`K`) that fold to ASCII.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidId` under the same conditions as [`parse_id`]. The error
+    /// holds `id` as written, and its span indexes it. Only the error path
+    /// allocates.
+    pub fn parse(id: &'a str) -> Result<Self> {
+        split_id(id).map_err(|reason| TerseIdError::InvalidId {
+            id: id.to_string(),
+            reason,
+        })
+    }
+
+    /// Returns true if this ID has no child path segments.
+    #[must_use]
+    pub const fn is_root(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Returns the depth of this ID (number of child path segments).
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        if self.children.is_empty() {
+            0
+        } else {
+            self.children.bytes().filter(|&b| b == b'.').count() + 1
+        }
+    }
+
+    /// Returns an iterator over the child path segments.
+    #[must_use]
+    pub const fn child_segments(&self) -> ChildSegments<'a> {
+        ChildSegments {
+            rest: self.children,
+        }
+    }
+
+    /// Converts this borrowed view into an owned, lowercased `ParsedId`.
+    #[must_use]
+    pub fn to_owned(&self) -> ParsedId {
+        ParsedId {
+            prefix: self.prefix.to_lowercase(),
+            hash: self.hash.to_ascii_lowercase(),
+            child_path: self.child_segments().collect(),
+        }
+    }
+}
+
+impl From<ParsedIdRef<'_>> for ParsedId {
+    fn from(parsed: ParsedIdRef<'_>) -> Self {
+        parsed.to_owned()
+    }
+}
+
+impl fmt::Display for ParsedIdRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.prefix, self.hash)?;
+        if !self.children.is_empty() {
+            write!(f, ".{}", self.children)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the child path segments of a [`ParsedIdRef`].
+#[derive(Debug, Clone)]
+pub struct ChildSegments<'a> {
+    rest: &'a str,
+}
+
+impl Iterator for ChildSegments<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (segment, rest) = self.rest.split_once('.').unwrap_or((self.rest, ""));
+        self.rest = rest;
+        // Segments were validated by `split_id`, so this never yields `None` early.
+        segment.parse().ok()
+    }
+}
+
+/// Splits and validates an ID in place. Shared by `parse_id` and `ParsedIdRef::parse`.
+fn split_id(id: &str) -> core::result::Result<ParsedIdRef<'_>, InvalidIdReason> {
+    // Find the first dot (if any) - this marks the start of child path
+    let first_dot = id.find('.');
+
+    // Find the last dash before the child path (or at the end if no dot)
+    let search_end = first_dot.unwrap_or(id.len());
+    let last_dash = id[..search_end]
+        .rfind('-')
+        .ok_or(InvalidIdReason::MissingDash {
+            span: 0..search_end,
+        })?;
+
+    let prefix = &id[..last_dash];
+    let rest = &id[last_dash + 1..];
+    let hash_start = last_dash + 1;
+
+    // First segment is hash, anything after the first dot is child path
+    let (hash, children) = rest.split_once('.').unwrap_or((rest, ""));
+
+    // Validate hash
+    if hash.is_empty() {
+        return Err(InvalidIdReason::EmptyHash {
+            span: hash_start..hash_start,
+        });
+    }
+
+    // All characters must be base36
+    if let Some((offset, found)) = hash.char_indices().find(|&(_, c)| !is_base36(c)) {
+        let start = hash_start + offset;
+        return Err(InvalidIdReason::InvalidChar {
+            found,
+            span: start..start + found.len_utf8(),
+        });
+    }
+
+    // NOTE: The generator ensures new 4+ char hashes contain a digit (to
+    // avoid ambiguity with English words), but the parser must accept
+    // all-letter hashes for backward compatibility with IDs generated before
+    // that constraint was added.
+
+    // Validate child path segments
+    if rest.len() > hash.len() {
+        for (segment, span) in child_segment_spans(children, hash_start + hash.len() + 1) {
+            if let Err(err) = segment.parse::<u32>() {
+                return Err(match err.kind() {
+                    IntErrorKind::PosOverflow => InvalidIdReason::ChildNumberOverflow { span },
+                    _ => InvalidIdReason::BadChildSegment { span },
+                });
+            }
+        }
+    }
+
+    Ok(ParsedIdRef {
+        prefix,
+        hash,
+        children,
+    })
+}
+
 /// Parses a terseid ID string into a structured `ParsedId`.
 ///
 /// Parsing rules:
@@ -102,70 +514,302 @@ const fn is_base36(c: char) -> bool {
 /// - Hash validation:
 ///   - Must be non-empty
 ///   - All characters must be base36 (0-9, a-z)
-///   - At 3 chars: any base36 is valid
-///   - At 4+ chars: must contain at least one digit (avoids ambiguity with English words)
+///   - All-letter hashes of 4+ chars are accepted for backward compatibility;
+///     use [`ParseOptions::strict`] to reject them
 /// - Child path segments after dots must be valid u32 integers
 ///
+/// For hot paths that only need to validate or inspect IDs, see the
+/// allocation-free [`ParsedIdRef::parse`].
+///
 /// # Errors
 ///
 /// Returns `InvalidId` if:
 /// - No dash found
 /// - Empty hash
 /// - Invalid base36 characters in hash
-/// - 4+ char hash without a digit
 /// - Invalid u32 child path segments
+///
+/// The error holds `id` as written, and its span indexes it.
 pub fn parse_id(id: &str) -> Result<ParsedId> {
-    let id = id.to_lowercase();
-
-    // Find the first dot (if any) - this marks the start of child path
-    let first_dot = id.find('.');
-
-    // Find the last dash before the child path (or at the end if no dot)
-    let search_end = first_dot.unwrap_or(id.len());
-    let Some(last_dash) = id[..search_end].rfind('-') else {
-        return Err(TerseIdError::InvalidId { id });
+    let lowered = id.to_lowercase();
+    let parsed = match split_id(&lowered) {
+        Ok(parsed) => parsed,
+        Err(reason) => return Err(invalid_raw(id, reason)),
     };
 
-    let prefix = id[..last_dash].to_string();
-    let rest = &id[last_dash + 1..];
+    Ok(ParsedId {
+        prefix: parsed.prefix.to_string(),
+        hash: parsed.hash.to_string(),
+        child_path: parsed.child_segments().collect(),
+    })
+}
 
-    // Split by dots: first segment is hash, rest are child path
-    let segments: Vec<&str> = rest.split('.').collect();
-    let hash = segments[0];
+/// The `InvalidId` error for `raw`, given a reason whose span indexes
+/// `raw.to_lowercase()`. The span is moved onto `raw`, which the error holds.
+fn invalid_raw(raw: &str, mut reason: InvalidIdReason) -> TerseIdError {
+    if !raw.is_ascii() {
+        let span = reason.span_mut();
+        *span = raw_offset(raw, span.start, false)..raw_offset(raw, span.end, true);
+        if let InvalidIdReason::InvalidChar { found, span } = &mut reason
+            && let Some(raw_char) = raw[span.start..].chars().next()
+        {
+            *found = raw_char;
+        }
+    }
+    TerseIdError::InvalidId {
+        id: raw.to_string(),
+        reason,
+    }
+}
 
-    // Validate hash
-    if hash.is_empty() {
-        return Err(TerseIdError::InvalidId { id });
+/// Maps a byte offset in `raw.to_lowercase()` to one in `raw`. An offset
+/// inside a character whose lowercase form is longer rounds to that
+/// character's start, or its end if `round_up` is set.
+fn raw_offset(raw: &str, lowered_offset: usize, round_up: bool) -> usize {
+    let mut position = 0;
+    for (offset, c) in raw.char_indices() {
+        if lowered_offset <= position {
+            return offset;
+        }
+        let next = position + c.to_lowercase().map(char::len_utf8).sum::<usize>();
+        if lowered_offset < next {
+            return if round_up {
+                offset + c.len_utf8()
+            } else {
+                offset
+            };
+        }
+        position = next;
     }
+    raw.len()
+}
 
-    // All characters must be base36
-    if !hash.chars().all(is_base36) {
-        return Err(TerseIdError::InvalidId { id });
+/// Compares two ID strings using the same ordering as `ParsedId`.
+///
+/// Both inputs are parsed, so comparison is case-insensitive and child paths
+/// compare numerically (`bd-a7x.2` < `bd-a7x.10`). Invalid IDs sort after all
+/// valid ones, and are compared by their lowercased text among themselves.
+#[must_use]
+pub fn compare_ids(a: &str, b: &str) -> Ordering {
+    match (parse_id(a), parse_id(b)) {
+        (Ok(parsed_a), Ok(parsed_b)) => parsed_a.cmp(&parsed_b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => normalize_id(a).cmp(&normalize_id(b)),
     }
+}
 
-    // NOTE: The generator ensures new 4+ char hashes contain a digit (to
-    // avoid ambiguity with English words), but the parser must accept
-    // all-letter hashes for backward compatibility with IDs generated before
-    // that constraint was added.
+/// How strictly [`parse_id_with`] treats non-canonical input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseMode {
+    /// Accept everything `parse_id` accepts (the default).
+    #[default]
+    Lenient,
+    /// Accept only IDs in the exact form `IdGenerator` produces.
+    Strict,
+}
 
-    // Parse child path segments
-    let mut child_path = Vec::new();
-    for segment_str in &segments[1..] {
-        match segment_str.parse::<u32>() {
-            Ok(num) => child_path.push(num),
-            Err(_) => {
-                return Err(TerseIdError::InvalidId { id });
-            }
+/// Options for [`parse_id_with`].
+///
+/// # Examples
+///
+/// ```
+/// use terseid::{ParseOptions, parse_id_with};
+///
+/// assert!(parse_id_with("BD-A7X.01", &ParseOptions::lenient()).is_ok());
+/// assert!(parse_id_with("BD-A7X.01", &ParseOptions::strict()).is_err());
+/// assert!(parse_id_with("bd-a7x.1", &ParseOptions::strict()).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    pub child_policy: ChildPolicy,
+}
+
+impl ParseOptions {
+    /// Lenient parsing with no child policy, same as `parse_id`.
+    #[must_use]
+    pub const fn lenient() -> Self {
+        Self {
+            mode: ParseMode::Lenient,
+            child_policy: ChildPolicy::new(),
         }
     }
 
-    Ok(ParsedId {
-        prefix,
-        hash: hash.to_string(),
-        child_path,
+    /// Strict parsing: only canonical IDs are accepted. See [`is_canonical`].
+    #[must_use]
+    pub const fn strict() -> Self {
+        Self {
+            mode: ParseMode::Strict,
+            child_policy: ChildPolicy::new(),
+        }
+    }
+
+    /// Sets how strictly non-canonical input is treated.
+    #[must_use]
+    pub const fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the child policy that parsed IDs must satisfy.
+    #[must_use]
+    pub const fn child_policy(mut self, policy: ChildPolicy) -> Self {
+        self.child_policy = policy;
+        self
+    }
+}
+
+/// Parses an ID with the given [`ParseOptions`].
+///
+/// In [`ParseMode::Strict`] the input must already be canonical (see
+/// [`is_canonical`]); it is not lowercased, and the error reports it as given.
+///
+/// # Errors
+///
+/// Returns `InvalidId` if the ID cannot be parsed, or is not canonical in strict mode.
+/// Returns `InvalidId` with an [`InvalidIdReason::Policy`] reason if the child
+/// path breaks the options' child policy.
+pub fn parse_id_with(id: &str, options: &ParseOptions) -> Result<ParsedId> {
+    if options.mode == ParseMode::Strict
+        && let Some(reason) = canonical_violation(id)
+    {
+        return Err(TerseIdError::InvalidId {
+            id: id.to_string(),
+            reason,
+        });
+    }
+    parse_id_with_policy(id, &options.child_policy)
+}
+
+/// Returns true if `id` is in the exact form `IdGenerator` and `child_id` produce.
+///
+/// On top of the `parse_id` rules, a canonical ID:
+/// - is ASCII and contains no uppercase letters (so `K`, the Kelvin sign, is rejected)
+/// - has a non-empty prefix
+/// - has at least one digit in hashes of 4+ characters
+/// - has child segments written as plain decimal without leading zeros (`0` itself is fine)
+///
+/// Every canonical ID round-trips unchanged through `parse_id` and `to_id_string`.
+#[must_use]
+pub fn is_canonical(id: &str) -> bool {
+    canonical_violation(id).is_none()
+}
+
+/// The first rule of [`is_canonical`] that `id` breaks, if any.
+fn canonical_violation(id: &str) -> Option<InvalidIdReason> {
+    if let Some((start, found)) = id
+        .char_indices()
+        .find(|&(_, c)| !c.is_ascii() || c.is_ascii_uppercase())
+    {
+        return Some(InvalidIdReason::InvalidChar {
+            found,
+            span: start..start + found.len_utf8(),
+        });
+    }
+    let raw = match split_id(id) {
+        Ok(raw) => raw,
+        Err(reason) => return Some(reason),
+    };
+    if raw.prefix.is_empty() {
+        return Some(InvalidIdReason::EmptyPrefix { span: 0..0 });
+    }
+    let hash_start = raw.prefix.len() + 1;
+    if raw.hash.len() >= 4 && !raw.hash.bytes().any(|b| b.is_ascii_digit()) {
+        return Some(InvalidIdReason::HashWithoutDigit {
+            span: hash_start..hash_start + raw.hash.len(),
+        });
+    }
+    if raw.children.is_empty() {
+        return None;
+    }
+    child_segment_spans(raw.children, hash_start + raw.hash.len() + 1).find_map(
+        |(segment, span)| {
+            if !segment.bytes().all(|b| b.is_ascii_digit()) {
+                Some(InvalidIdReason::BadChildSegment { span })
+            } else if segment.len() > 1 && segment.starts_with('0') {
+                Some(InvalidIdReason::Policy {
+                    violation: ChildPolicyViolation::LeadingZero {
+                        segment: segment.to_string(),
+                    },
+                    span,
+                })
+            } else {
+                None
+            }
+        },
+    )
+}
+
+/// Pairs each dot-separated child segment with its byte span in the full ID.
+fn child_segment_spans(children: &str, start: usize) -> impl Iterator<Item = (&str, Range<usize>)> {
+    children.split('.').scan(start, |next, segment| {
+        let span = *next..*next + segment.len();
+        *next = span.end + 1;
+        Some((segment, span))
     })
 }
 
+/// Parses an ID and enforces a [`ChildPolicy`] on its child path.
+///
+/// # Errors
+///
+/// Returns `InvalidId` if the ID cannot be parsed.
+/// Returns `InvalidId` with an [`InvalidIdReason::Policy`] reason if the child
+/// path breaks `policy`; unlike [`try_child_id`](crate::children::try_child_id),
+/// it never returns `PolicyViolation`. The error holds `id` as written, and the
+/// span covers the offending segment, or for a path that is too deep, the
+/// segments past the limit.
+pub fn parse_id_with_policy(id: &str, policy: &ChildPolicy) -> Result<ParsedId> {
+    let parsed = parse_id(id)?;
+    if parsed.is_root() {
+        return Ok(parsed);
+    }
+    let lowered = id.to_lowercase();
+    let children_start = parsed.prefix.len() + parsed.hash.len() + 2;
+    let segments: Vec<_> =
+        child_segment_spans(&lowered[children_start..], children_start).collect();
+
+    let violation = segments
+        .iter()
+        .find_map(|(segment, span)| {
+            policy
+                .check_segment_text(segment)
+                .err()
+                .map(|violation| (violation, span.clone()))
+        })
+        .or_else(|| {
+            let max_depth = policy.max_depth?;
+            let (_, first_extra) = segments.get(max_depth)?;
+            Some((
+                ChildPolicyViolation::TooDeep {
+                    depth: segments.len(),
+                    max_depth,
+                },
+                first_extra.start..lowered.len(),
+            ))
+        })
+        .or_else(|| {
+            segments
+                .iter()
+                .zip(&parsed.child_path)
+                .find_map(|((_, span), &number)| {
+                    policy
+                        .check_number(number)
+                        .err()
+                        .map(|violation| (violation, span.clone()))
+                })
+        });
+    match violation {
+        None => Ok(parsed),
+        Some((violation, span)) => {
+            Err(invalid_raw(id, InvalidIdReason::Policy { violation, span }))
+        }
+    }
+}
+
 /// Returns true if the given ID string is in a valid format.
 ///
 /// This is a convenience function that tries to parse the ID and returns
@@ -355,6 +999,137 @@ mod tests {
         assert!(parse_id("bd-a7x.-1").is_err());
     }
 
+    // ========== InvalidId reasons ==========
+
+    fn reason(id: &str) -> InvalidIdReason {
+        match parse_id(id) {
+            Err(TerseIdError::InvalidId { reason, .. }) => reason,
+            other => panic!("expected InvalidId for {id}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reason_missing_dash() {
+        assert_eq!(reason("bda7x"), InvalidIdReason::MissingDash { span: 0..5 });
+        assert_eq!(
+            reason("bda7x.1-2"),
+            InvalidIdReason::MissingDash { span: 0..5 }
+        );
+    }
+
+    #[test]
+    fn test_reason_empty_hash() {
+        assert_eq!(reason("bd-"), InvalidIdReason::EmptyHash { span: 3..3 });
+        assert_eq!(reason("bd-.1"), InvalidIdReason::EmptyHash { span: 3..3 });
+    }
+
+    #[test]
+    fn test_reason_invalid_char() {
+        assert_eq!(
+            reason("bd-a7x!"),
+            InvalidIdReason::InvalidChar {
+                found: '!',
+                span: 6..7
+            }
+        );
+        assert_eq!(
+            reason("bd-a\u{e9}x"),
+            InvalidIdReason::InvalidChar {
+                found: '\u{e9}',
+                span: 4..6
+            }
+        );
+    }
+
+    #[test]
+    fn test_reason_child_segments() {
+        assert_eq!(
+            reason("bd-a7x.1.abc"),
+            InvalidIdReason::BadChildSegment { span: 9..12 }
+        );
+        assert_eq!(
+            reason("bd-a7x..1"),
+            InvalidIdReason::BadChildSegment { span: 7..7 }
+        );
+        assert_eq!(
+            reason("bd-a7x.1."),
+            InvalidIdReason::BadChildSegment { span: 9..9 }
+        );
+        assert_eq!(
+            reason("bd-a7x.4294967296"),
+            InvalidIdReason::ChildNumberOverflow { span: 7..17 }
+        );
+    }
+
+    #[test]
+    fn test_reason_pointer_from_parse() {
+        let err = parse_id("BD-A7X.1.X").unwrap_err();
+        assert_eq!(
+            err.pointer().unwrap().to_string(),
+            "BD-A7X.1.X\n         ^ child segment at byte 9 is not a number"
+        );
+    }
+
+    #[test]
+    fn test_reason_spans_index_raw_input() {
+        // The Kelvin sign is three bytes but lowercases to a one-byte `k`.
+        let err = parse_id("\u{212A}d-a7x.1x").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::InvalidId {
+                id: "\u{212A}d-a7x.1x".to_string(),
+                reason: InvalidIdReason::BadChildSegment { span: 9..11 },
+            }
+        );
+        assert_eq!(
+            err.pointer().unwrap().to_string(),
+            "\u{212A}d-a7x.1x\n       ^^ child segment at byte 9 is not a number"
+        );
+
+        // `İ` lowercases to `i` plus a combining dot, which is not base36.
+        let err = parse_id("bd-\u{130}a").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::InvalidId {
+                id: "bd-\u{130}a".to_string(),
+                reason: InvalidIdReason::InvalidChar {
+                    found: '\u{130}',
+                    span: 3..5,
+                },
+            }
+        );
+        assert_eq!(
+            err.pointer().unwrap().to_string(),
+            "bd-\u{130}a\n   ^ unexpected character '\u{130}' at byte 3"
+        );
+    }
+
+    #[test]
+    fn test_strict_reasons() {
+        let strict = |id: &str| match parse_id_with(id, &ParseOptions::strict()) {
+            Err(TerseIdError::InvalidId { reason, .. }) => reason,
+            other => panic!("expected InvalidId for {id}, got {other:?}"),
+        };
+        assert_eq!(strict("-a7x"), InvalidIdReason::EmptyPrefix { span: 0..0 });
+        assert_eq!(
+            strict("bd-test"),
+            InvalidIdReason::HashWithoutDigit { span: 3..7 }
+        );
+        assert_eq!(
+            strict("bd-a7x.+1"),
+            InvalidIdReason::BadChildSegment { span: 7..9 }
+        );
+        assert_eq!(
+            strict("bd-a7x.1.007"),
+            InvalidIdReason::Policy {
+                violation: ChildPolicyViolation::LeadingZero {
+                    segment: "007".to_string()
+                },
+                span: 9..12
+            }
+        );
+    }
+
     // ========== Round-trip tests ==========
 
     #[test]
@@ -665,4 +1440,463 @@ mod tests {
         assert!(debug_str.contains("bd"));
         assert!(debug_str.contains("a7x"));
     }
+
+    // ========== ancestors / common_ancestor / relative_path ==========
+
+    #[test]
+    fn test_ancestors_nearest_first() {
+        let id = parse_id("bd-a7x.1.3.7").unwrap();
+        let ancestors: Vec<String> = id.ancestors().map(|a| a.to_id_string()).collect();
+        assert_eq!(ancestors, vec!["bd-a7x.1.3", "bd-a7x.1", "bd-a7x"]);
+        assert_eq!(id.ancestors().len(), 3);
+    }
+
+    #[test]
+    fn test_ancestors_rev_root_first() {
+        let id = parse_id("bd-a7x.1.3").unwrap();
+        let crumbs: Vec<String> = id.ancestors().rev().map(|a| a.to_string()).collect();
+        assert_eq!(crumbs, vec!["bd-a7x", "bd-a7x.1"]);
+    }
+
+    #[test]
+    fn test_ancestors_root_is_empty() {
+        let id = parse_id("bd-a7x").unwrap();
+        assert_eq!(id.ancestors().next(), None);
+    }
+
+    #[test]
+    fn test_ancestors_borrow_and_match_parent() {
+        let id = parse_id("my-proj-a7x.2.5").unwrap();
+        let parent = id.ancestors().next().unwrap();
+        assert!(core::ptr::eq(parent.hash, id.hash.as_str()));
+        assert_eq!(Some(parent.to_id_string()), id.parent());
+        assert_eq!(parent.to_owned(), parse_id("my-proj-a7x.2").unwrap());
+        assert_eq!(parent, parse_id("my-proj-a7x.2").unwrap());
+        assert_eq!(id.as_ancestor(), id);
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        let a = parse_id("bd-a7x.1.3.7").unwrap();
+        let b = parse_id("bd-a7x.1.4").unwrap();
+        let common = ParsedId::common_ancestor(&a, &b).unwrap();
+        assert_eq!(common.to_string(), "bd-a7x.1");
+        assert_eq!(common.depth(), 1);
+    }
+
+    #[test]
+    fn test_common_ancestor_inclusive() {
+        let parent = parse_id("bd-a7x.1").unwrap();
+        let child = parse_id("bd-a7x.1.2").unwrap();
+        assert_eq!(ParsedId::common_ancestor(&parent, &child).unwrap(), parent);
+        assert_eq!(ParsedId::common_ancestor(&child, &child).unwrap(), child);
+    }
+
+    #[test]
+    fn test_common_ancestor_roots_and_other_trees() {
+        let a = parse_id("bd-a7x.1").unwrap();
+        let b = parse_id("bd-a7x.2").unwrap();
+        assert!(ParsedId::common_ancestor(&a, &b).unwrap().is_root());
+        assert_eq!(
+            ParsedId::common_ancestor(&a, &parse_id("bd-k2m.1").unwrap()),
+            None
+        );
+        assert_eq!(
+            ParsedId::common_ancestor(&a, &parse_id("tk-a7x.1").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let from = parse_id("bd-a7x.1.3.7").unwrap();
+        let to = parse_id("bd-a7x.1.4.2").unwrap();
+        let path = ParsedId::relative_path(&from, &to).unwrap();
+        assert_eq!(path.up, 2);
+        assert_eq!(path.down, &[4, 2]);
+        assert_eq!(path.to_string(), "../../4/2");
+    }
+
+    #[test]
+    fn test_relative_path_ancestor_and_descendant() {
+        let parent = parse_id("bd-a7x.1").unwrap();
+        let grandchild = parse_id("bd-a7x.1.3.5").unwrap();
+        let down = ParsedId::relative_path(&parent, &grandchild).unwrap();
+        assert_eq!(down.to_string(), "3/5");
+        let up = ParsedId::relative_path(&grandchild, &parent).unwrap();
+        assert_eq!(up.to_string(), "../..");
+    }
+
+    #[test]
+    fn test_relative_path_same_and_other_tree() {
+        let id = parse_id("bd-a7x.1").unwrap();
+        let same = ParsedId::relative_path(&id, &id).unwrap();
+        assert!(same.is_same());
+        assert_eq!(same.to_string(), ".");
+        assert_eq!(
+            ParsedId::relative_path(&id, &parse_id("bd-k2m").unwrap()),
+            None
+        );
+    }
+
+    // ========== parse_id_with_policy ==========
+
+    #[test]
+    fn test_parse_with_default_policy_matches_parse_id() {
+        let policy = ChildPolicy::new();
+        for id in ["bd-a7x", "bd-a7x.01", "bd-a7x.0.1.2.3.4.5"] {
+            assert_eq!(parse_id_with_policy(id, &policy), parse_id(id));
+        }
+        assert!(parse_id_with_policy("bd-a7x.x", &policy).is_err());
+    }
+
+    #[test]
+    fn test_parse_with_policy_leading_zero() {
+        let policy = ChildPolicy::new().allow_leading_zeros(false);
+        assert!(parse_id_with_policy("bd-a7x.10.0", &policy).is_ok());
+        assert_eq!(
+            parse_id_with_policy("BD-A7X.01", &policy),
+            Err(TerseIdError::InvalidId {
+                id: "BD-A7X.01".to_string(),
+                reason: InvalidIdReason::Policy {
+                    violation: ChildPolicyViolation::LeadingZero {
+                        segment: "01".to_string()
+                    },
+                    span: 7..9,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_with_policy_depth_zero_and_max() {
+        let policy = ChildPolicy::new()
+            .max_depth(2)
+            .allow_zero(false)
+            .max_child_number(50);
+        assert!(parse_id_with_policy("bd-a7x.1.50", &policy).is_ok());
+        let pointer = |id: &str| {
+            parse_id_with_policy(id, &policy)
+                .unwrap_err()
+                .pointer()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            pointer("bd-a7x.1.2.3"),
+            "bd-a7x.1.2.3\n           ^ depth 3 exceeds maximum of 2"
+        );
+        assert_eq!(
+            pointer("bd-a7x.1.2.3.4"),
+            "bd-a7x.1.2.3.4\n           ^^^ depth 4 exceeds maximum of 2"
+        );
+        assert_eq!(
+            pointer("bd-a7x.1.0"),
+            "bd-a7x.1.0\n         ^ child number 0 is not allowed"
+        );
+        assert_eq!(
+            pointer("bd-a7x.51"),
+            "bd-a7x.51\n       ^^ child number 51 exceeds maximum of 50"
+        );
+        assert_eq!(
+            pointer("\u{212A}d-a7x.1.0"),
+            "\u{212A}d-a7x.1.0\n         ^ child number 0 is not allowed"
+        );
+        // Policy failures always come back as `InvalidId`, never `PolicyViolation`.
+        for id in ["bd-a7x.1.2.3", "bd-a7x.1.0", "bd-a7x.51"] {
+            assert!(matches!(
+                parse_id_with_policy(id, &policy),
+                Err(TerseIdError::InvalidId {
+                    reason: InvalidIdReason::Policy { .. },
+                    ..
+                })
+            ));
+        }
+    }
+
+    // ========== ParseOptions / is_canonical ==========
+
+    #[test]
+    fn test_is_canonical_accepts_generated_forms() {
+        for id in [
+            "bd-a7x",
+            "bd-abc",
+            "bd-a7x3q9",
+            "my-proj-a7x3q9",
+            "bd-a7x.1.3",
+            "bd-a7x.0",
+            "bd-a7x.10",
+        ] {
+            assert!(is_canonical(id), "{id}");
+            assert_eq!(parse_id(id).unwrap().to_id_string(), id);
+        }
+    }
+
+    #[test]
+    fn test_is_canonical_rejects_non_canonical() {
+        for id in [
+            "BD-a7x",
+            "bd-A7X",
+            "bd-a7x.01",
+            "bd-a7x.+1",
+            "bd-abcd",
+            "-a7x",
+            "bd-a7\u{212a}",
+            "bd-a7x.",
+            "bd",
+        ] {
+            assert!(!is_canonical(id), "{id}");
+        }
+        // Kelvin sign folds to 'k' under lenient parsing
+        assert!(parse_id("bd-a7\u{212a}").is_ok());
+        assert!(!is_canonical("bd-a7\u{212a}"));
+    }
+
+    #[test]
+    fn test_parse_id_with_lenient_matches_parse_id() {
+        let options = ParseOptions::default();
+        assert_eq!(options, ParseOptions::lenient());
+        for id in ["BD-A7X.01", "bd-abcd", "bd-a7x.1", "nope"] {
+            assert_eq!(parse_id_with(id, &options), parse_id(id));
+        }
+    }
+
+    #[test]
+    fn test_parse_id_with_strict() {
+        let options = ParseOptions::strict();
+        assert_eq!(
+            parse_id_with("bd-a7x.2", &options).unwrap(),
+            parse_id("bd-a7x.2").unwrap()
+        );
+        assert_eq!(
+            parse_id_with("BD-A7X", &options),
+            Err(TerseIdError::InvalidId {
+                id: "BD-A7X".to_string(),
+                reason: InvalidIdReason::InvalidChar {
+                    found: 'B',
+                    span: 0..1
+                },
+            })
+        );
+        assert!(parse_id_with("bd-test", &options).is_err());
+        assert_eq!(
+            ParseOptions::lenient().mode(ParseMode::Strict),
+            ParseOptions::strict()
+        );
+    }
+
+    #[test]
+    fn test_parse_id_with_child_policy() {
+        let options = ParseOptions::strict().child_policy(ChildPolicy::new().max_depth(1));
+        assert!(parse_id_with("bd-a7x.1", &options).is_ok());
+        assert!(matches!(
+            parse_id_with("bd-a7x.1.1", &options),
+            Err(TerseIdError::InvalidId {
+                reason: InvalidIdReason::Policy { .. },
+                ..
+            })
+        ));
+    }
+
+    // ========== FromStr and ordering ==========
+
+    #[test]
+    fn test_from_str() {
+        let parsed: ParsedId = "BD-A7X.2".parse().unwrap();
+        assert_eq!(parsed, parse_id("bd-a7x.2").unwrap());
+        assert!("bda7x".parse::<ParsedId>().is_err());
+    }
+
+    #[test]
+    fn test_ord_child_path_numeric() {
+        let two: ParsedId = "bd-a7x.2".parse().unwrap();
+        let ten: ParsedId = "bd-a7x.10".parse().unwrap();
+        assert!(two < ten);
+    }
+
+    #[test]
+    fn test_ord_parent_before_descendants() {
+        let mut ids: Vec<ParsedId> = [
+            "bd-a7y",
+            "bd-a7x.10",
+            "bd-a7x.2",
+            "bd-a7x.1.3",
+            "bd-a7x",
+            "bd-a7x.1",
+            "ab-zzz",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+        ids.sort();
+        let sorted: Vec<String> = ids.iter().map(ParsedId::to_id_string).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                "ab-zzz",
+                "bd-a7x",
+                "bd-a7x.1",
+                "bd-a7x.1.3",
+                "bd-a7x.2",
+                "bd-a7x.10",
+                "bd-a7y",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_btreemap_and_hash_keys() {
+        let mut map = alloc::collections::BTreeMap::new();
+        map.insert(parse_id("bd-a7x.10").unwrap(), 10);
+        map.insert(parse_id("bd-a7x.2").unwrap(), 2);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec![2, 10]);
+
+        let set: std::collections::HashSet<ParsedId> = ["bd-a7x", "BD-A7X"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_compare_ids() {
+        assert_eq!(compare_ids("bd-a7x.2", "bd-a7x.10"), Ordering::Less);
+        assert_eq!(compare_ids("bd-a7x", "bd-a7x.1"), Ordering::Less);
+        assert_eq!(compare_ids("BD-A7X.1", "bd-a7x.1"), Ordering::Equal);
+        assert_eq!(compare_ids("bd-a7y", "bd-a7x.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_compare_ids_invalid_sorts_last() {
+        assert_eq!(compare_ids("bd-a7x", "invalid"), Ordering::Less);
+        assert_eq!(compare_ids("invalid", "bd-a7x"), Ordering::Greater);
+        assert_eq!(compare_ids("aaa", "bbb"), Ordering::Less);
+
+        let mut ids = vec!["zzz", "bd-a7x.10", "bd-a7x.2"];
+        ids.sort_by(|a, b| compare_ids(a, b));
+        assert_eq!(ids, vec!["bd-a7x.2", "bd-a7x.10", "zzz"]);
+    }
+
+    // ========== ParsedIdRef ==========
+
+    #[test]
+    fn test_ref_simple() {
+        let parsed = ParsedIdRef::parse("bd-a7x").unwrap();
+        assert_eq!(parsed.prefix, "bd");
+        assert_eq!(parsed.hash, "a7x");
+        assert!(parsed.is_root());
+        assert_eq!(parsed.depth(), 0);
+        assert_eq!(parsed.child_segments().count(), 0);
+    }
+
+    #[test]
+    fn test_ref_borrows_input_as_written() {
+        let input = "My-Proj-A7X3Q9.1.3";
+        let parsed = ParsedIdRef::parse(input).unwrap();
+        assert_eq!(parsed.prefix, "My-Proj");
+        assert_eq!(parsed.hash, "A7X3Q9");
+        assert!(core::ptr::eq(parsed.prefix.as_ptr(), input.as_ptr()));
+        assert_eq!(parsed.to_string(), input);
+    }
+
+    #[test]
+    fn test_ref_child_segments() {
+        let parsed = ParsedIdRef::parse("bd-a7x.1.30.7").unwrap();
+        assert!(!parsed.is_root());
+        assert_eq!(parsed.depth(), 3);
+        assert_eq!(parsed.child_segments().collect::<Vec<_>>(), vec![1, 30, 7]);
+    }
+
+    #[test]
+    fn test_ref_to_owned_normalizes() {
+        let parsed = ParsedIdRef::parse("BD-A7X.01.3").unwrap();
+        assert_eq!(parsed.to_owned(), parse_id("bd-a7x.1.3").unwrap());
+        assert_eq!(ParsedId::from(parsed).to_id_string(), "bd-a7x.1.3");
+    }
+
+    #[test]
+    fn test_ref_invalid() {
+        for id in [
+            "bda7x",
+            "bd-",
+            "bd-a7x!",
+            "bd-a7x.",
+            "bd-a7x..1",
+            "bd-a7x.abc",
+            "bd-a7x.-1",
+        ] {
+            assert_eq!(
+                ParsedIdRef::parse(id).unwrap_err(),
+                parse_id(id).unwrap_err(),
+                "{id}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ref_invalid_reports_raw_input() {
+        for id in ["BD-A7X!", "\u{130}-a7x!", "bd-a\u{212a}x"] {
+            let Err(TerseIdError::InvalidId {
+                id: reported,
+                reason,
+            }) = ParsedIdRef::parse(id)
+            else {
+                panic!("{id} should be invalid");
+            };
+            assert_eq!(reported, id);
+            assert!(id.get(reason.span()).is_some(), "{id}: {:?}", reason.span());
+        }
+        let err = ParsedIdRef::parse("\u{130}-a7x!").unwrap_err();
+        assert_eq!(
+            err.pointer().unwrap().to_string(),
+            "\u{130}-a7x!\n     ^ unexpected character '!' at byte 6"
+        );
+    }
+
+    #[test]
+    fn test_ref_rejects_non_ascii_hash() {
+        // parse_id folds the Kelvin sign to 'k'; the borrowed parser cannot.
+        assert!(parse_id("bd-a\u{212a}x").is_ok());
+        assert!(ParsedIdRef::parse("bd-a\u{212a}x").is_err());
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::proptest;
+
+        proptest! {
+            #[test]
+            fn ref_agrees_with_parse_id(id in "[a-zA-Z0-9.+-]{0,16}") {
+                // Both report errors against the input as written.
+                assert_eq!(parse_id(&id), ParsedIdRef::parse(&id).map(|parsed| parsed.to_owned()));
+            }
+        }
+    }
+
+    // ========== serde ==========
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_serializes_as_string() {
+        let parsed = parse_id("my-proj-a7x3q9.1.3").unwrap();
+        let json = serde_json::to_string(&parsed).unwrap();
+        assert_eq!(json, "\"my-proj-a7x3q9.1.3\"");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip_normalizes_case() {
+        let parsed: ParsedId = serde_json::from_str("\"BD-A7X.2\"").unwrap();
+        assert_eq!(parsed, parse_id("bd-a7x.2").unwrap());
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), "\"bd-a7x.2\"");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_invalid_id() {
+        let err = serde_json::from_str::<ParsedId>("\"bd-a7x.abc\"").unwrap_err();
+        assert!(err.to_string().contains("invalid ID format"));
+        assert!(serde_json::from_str::<ParsedId>("\"bda7x\"").is_err());
+        assert!(serde_json::from_str::<ParsedId>("42").is_err());
+    }
 }
@@ -0,0 +1,306 @@
+//! Moving subtrees to a new parent.
+//!
+//! Moving `bd-a7x.3` under `bd-k2m` changes every ID in its subtree:
+//! `bd-a7x.3.1.2` becomes `bd-k2m.5.1.2`. [`reparent`] works out that mapping
+//! and returns it as a [`Remap`] that can be applied to stored IDs and
+//! references.
+
+use crate::children::ChildAllocator;
+use crate::error::{Result, TerseIdError};
+use crate::parse::{ParsedId, parse_id};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// An old-to-new ID mapping produced by [`reparent`].
+///
+/// The mapping covers the whole subtree, not just the IDs that were passed to
+/// `reparent`: any descendant of the old root can be remapped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remap {
+    old_root: ParsedId,
+    new_root: ParsedId,
+    moved: Vec<(ParsedId, ParsedId)>,
+}
+
+impl Remap {
+    /// Returns the root of the subtree before the move.
+    #[must_use]
+    pub const fn old_root(&self) -> &ParsedId {
+        &self.old_root
+    }
+
+    /// Returns the root of the subtree after the move.
+    #[must_use]
+    pub const fn new_root(&self) -> &ParsedId {
+        &self.new_root
+    }
+
+    /// Returns the number of known IDs that move, including the root.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.moved.len()
+    }
+
+    /// Returns true if no IDs move. Never true for a `Remap` from [`reparent`].
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.moved.is_empty()
+    }
+
+    /// Iterates over `(old, new)` pairs for the known IDs, in natural order of the old IDs.
+    pub fn iter(&self) -> impl Iterator<Item = (&ParsedId, &ParsedId)> {
+        self.moved.iter().map(|(old, new)| (old, new))
+    }
+
+    /// Remaps a parsed ID, or returns `None` if it is outside the moved subtree.
+    #[must_use]
+    pub fn apply_parsed(&self, id: &ParsedId) -> Option<ParsedId> {
+        let depth = self.old_root.depth();
+        if id.prefix != self.old_root.prefix
+            || id.hash != self.old_root.hash
+            || !id.child_path.starts_with(&self.old_root.child_path)
+        {
+            return None;
+        }
+        let mut remapped = self.new_root.clone();
+        remapped
+            .child_path
+            .extend_from_slice(&id.child_path[depth..]);
+        Some(remapped)
+    }
+
+    /// Remaps an ID string, or returns `None` if it is invalid or outside the moved subtree.
+    ///
+    /// The result is normalized (lowercased).
+    #[must_use]
+    pub fn apply(&self, id: &str) -> Option<String> {
+        let parsed = parse_id(id).ok()?;
+        self.apply_parsed(&parsed)
+            .map(|remapped| remapped.to_id_string())
+    }
+
+    /// Rewrites every ID in `ids` that lies in the moved subtree.
+    ///
+    /// Other entries are left untouched. Returns the number of IDs rewritten.
+    pub fn apply_all(&self, ids: &mut [String]) -> usize {
+        let mut rewritten = 0;
+        for id in ids {
+            if let Some(remapped) = self.apply(id) {
+                *id = remapped;
+                rewritten += 1;
+            }
+        }
+        rewritten
+    }
+}
+
+/// Computes the ID changes for moving `subtree_root` under `new_parent`.
+///
+/// The subtree root gets the next free child number under `new_parent`
+/// (highest existing child + 1), and every descendant keeps its path relative
+/// to the root. `existing` is the full set of IDs in storage: it decides the new
+/// child number and which IDs are listed by [`Remap::iter`]. The subtree being
+/// moved doesn't count towards the new parent's children, so moving a subtree
+/// under its current parent renumbers it as the newest child, and returns an
+/// identity mapping if it already is the highest-numbered one.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::reparent;
+///
+/// let existing = ["bd-a7x", "bd-a7x.3", "bd-a7x.3.1", "bd-k2m", "bd-k2m.4"];
+/// let remap = reparent("bd-a7x.3", "bd-k2m", &existing).unwrap();
+///
+/// assert_eq!(remap.apply("bd-a7x.3.1").unwrap(), "bd-k2m.5.1");
+/// assert_eq!(remap.apply("bd-a7x.2"), None);
+/// assert_eq!(remap.len(), 2);
+/// ```
+///
+/// # Errors
+///
+/// Returns `InvalidId` if `subtree_root`, `new_parent` or an entry of
+/// `existing` cannot be parsed.
+/// Returns `CyclicMove` if `new_parent` is `subtree_root` or one of its descendants.
+/// Returns `ChildNumbersExhausted` if `new_parent` has no child numbers left.
+pub fn reparent(
+    subtree_root: &str,
+    new_parent: &str,
+    existing: &[impl AsRef<str>],
+) -> Result<Remap> {
+    let old_root = parse_id(subtree_root)?;
+    let parent = parse_id(new_parent)?;
+    if in_subtree(&parent, &old_root) {
+        return Err(TerseIdError::CyclicMove {
+            id: old_root.to_id_string(),
+            new_parent: parent.to_id_string(),
+        });
+    }
+
+    let mut subtree = Vec::new();
+    let mut allocator = ChildAllocator::new(new_parent)?;
+    for id in existing {
+        let parsed = parse_id(id.as_ref())?;
+        if in_subtree(&parsed, &old_root) {
+            subtree.push(parsed);
+        } else if parsed != parent && in_subtree(&parsed, &parent) {
+            allocator.observe(id.as_ref())?;
+        }
+    }
+
+    let new_root = parse_id(&allocator.allocate()?)?;
+    let mut remap = Remap {
+        old_root,
+        new_root,
+        moved: Vec::new(),
+    };
+
+    subtree.push(remap.old_root.clone());
+    subtree.sort_unstable();
+    subtree.dedup();
+    remap.moved = subtree
+        .into_iter()
+        .filter_map(|old| remap.apply_parsed(&old).map(|new| (old, new)))
+        .collect();
+    Ok(remap)
+}
+
+/// True if `id` is `root` or one of its descendants.
+fn in_subtree(id: &ParsedId, root: &ParsedId) -> bool {
+    id.prefix == root.prefix && id.hash == root.hash && id.child_path.starts_with(&root.child_path)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn pairs(remap: &Remap) -> Vec<(String, String)> {
+        remap
+            .iter()
+            .map(|(old, new)| (old.to_string(), new.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reparent_maps_whole_subtree() {
+        let existing = [
+            "bd-a7x",
+            "bd-a7x.3",
+            "bd-a7x.3.1",
+            "bd-a7x.3.1.2",
+            "bd-a7x.3.2",
+            "bd-a7x.4",
+            "bd-k2m",
+            "bd-k2m.4",
+        ];
+        let remap = reparent("bd-a7x.3", "bd-k2m", &existing).unwrap();
+        assert_eq!(remap.old_root().to_string(), "bd-a7x.3");
+        assert_eq!(remap.new_root().to_string(), "bd-k2m.5");
+        assert_eq!(
+            pairs(&remap),
+            vec![
+                ("bd-a7x.3".to_string(), "bd-k2m.5".to_string()),
+                ("bd-a7x.3.1".to_string(), "bd-k2m.5.1".to_string()),
+                ("bd-a7x.3.1.2".to_string(), "bd-k2m.5.1.2".to_string()),
+                ("bd-a7x.3.2".to_string(), "bd-k2m.5.2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reparent_root_id() {
+        let remap = reparent("bd-a7x", "bd-k2m.2", &["bd-a7x.1", "bd-a7x"]).unwrap();
+        assert_eq!(remap.new_root().to_string(), "bd-k2m.2.1");
+        assert_eq!(remap.apply("bd-a7x.1").unwrap(), "bd-k2m.2.1.1");
+        assert_eq!(remap.len(), 2);
+    }
+
+    #[test]
+    fn test_reparent_root_not_in_existing() {
+        let existing: [&str; 0] = [];
+        let remap = reparent("bd-a7x.3", "bd-k2m", &existing).unwrap();
+        assert_eq!(remap.len(), 1);
+        assert!(!remap.is_empty());
+        assert_eq!(remap.new_root().to_string(), "bd-k2m.1");
+    }
+
+    #[test]
+    fn test_reparent_under_current_parent_renumbers() {
+        let existing = ["bd-a7x.1", "bd-a7x.2", "bd-a7x.2.1"];
+        let remap = reparent("bd-a7x.1", "bd-a7x", &existing).unwrap();
+        assert_eq!(remap.new_root().to_string(), "bd-a7x.3");
+    }
+
+    #[test]
+    fn test_reparent_newest_child_under_current_parent_is_identity() {
+        let existing = ["bd-a7x.1", "bd-a7x.2", "bd-a7x.2.1"];
+        let remap = reparent("bd-a7x.2", "bd-a7x", &existing).unwrap();
+        assert_eq!(remap.new_root().to_string(), "bd-a7x.2");
+        assert!(remap.iter().all(|(old, new)| old == new));
+        assert_eq!(remap.len(), 2);
+    }
+
+    #[test]
+    fn test_reparent_into_own_subtree() {
+        let err = reparent("bd-a7x.3", "BD-A7X.3.1", &["bd-a7x.3.1"]).unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::CyclicMove {
+                id: "bd-a7x.3".to_string(),
+                new_parent: "bd-a7x.3.1".to_string(),
+            }
+        );
+        assert!(reparent("bd-a7x.3", "bd-a7x.3", &["bd-a7x.3"]).is_err());
+    }
+
+    #[test]
+    fn test_reparent_invalid_inputs() {
+        let existing = ["bd-a7x.3"];
+        assert!(matches!(
+            reparent("nope", "bd-k2m", &existing),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+        assert!(matches!(
+            reparent("bd-a7x.3", "nope", &existing),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+        assert!(matches!(
+            reparent("bd-a7x.3", "bd-k2m", &["garbage", "bd-a7x.3.1"]),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_apply_outside_subtree() {
+        let remap = reparent("bd-a7x.3", "bd-k2m", &["bd-a7x.3"]).unwrap();
+        assert_eq!(remap.apply("bd-a7x"), None);
+        assert_eq!(remap.apply("bd-a7x.30"), None);
+        assert_eq!(remap.apply("bd-b8y.3"), None);
+        assert_eq!(remap.apply("not an id"), None);
+        assert_eq!(remap.apply("BD-A7X.3.9").unwrap(), "bd-k2m.1.9");
+    }
+
+    #[test]
+    fn test_apply_parsed() {
+        let remap = reparent("bd-a7x.3", "tk-r2m", &["bd-a7x.3"]).unwrap();
+        let id = parse_id("bd-a7x.3.2").unwrap();
+        assert_eq!(
+            remap.apply_parsed(&id),
+            Some(parse_id("tk-r2m.1.2").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_apply_all() {
+        let existing = ["bd-a7x.3", "bd-a7x.3.1", "bd-k2m.1"];
+        let remap = reparent("bd-a7x.3", "bd-k2m", &existing).unwrap();
+        let mut ids: Vec<String> = ["bd-a7x.3.1", "bd-a7x", "bd-a7x.3", "bd-k2m.1"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(remap.apply_all(&mut ids), 2);
+        assert_eq!(ids, vec!["bd-k2m.2.1", "bd-a7x", "bd-k2m.2", "bd-k2m.1"]);
+    }
+}
@@ -1,15 +1,50 @@
 use crate::error::{Result, TerseIdError};
-use crate::parse::parse_id;
+use crate::index::IdIndex;
+use crate::normalize::{InputNormalizer, Normalization};
+use crate::parse::{ParsedId, parse_id};
+use crate::stage::{BUILT_IN_STAGES, ResolutionStage, StageContext};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
 
 /// Configuration for the ID resolver.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResolverConfig {
     /// Default prefix to prepend when normalizing IDs without a dash.
     pub default_prefix: String,
-    /// List of allowed prefixes for validation.
+    /// Prefixes accepted in addition to `default_prefix`. Resolution never
+    /// returns an ID with any other prefix.
     pub allowed_prefixes: Vec<String>,
     /// Whether to allow substring matching in resolution.
     pub allow_substring_match: bool,
+    /// Whether prefix normalization also tries each of `allowed_prefixes`, in
+    /// order, after `default_prefix`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub try_allowed_prefixes: bool,
+    /// How input is cleaned up before matching.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub normalizer: InputNormalizer,
+    /// "Did you mean" suggestions for input that matches nothing. Off when `None`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fuzzy: Option<FuzzyConfig>,
+    /// Maximum number of candidates kept in an `AmbiguousId` error, best
+    /// first. Defaults to 10; the error's `total` still counts all of them.
+    #[cfg_attr(feature = "serde", serde(default = "default_max_ambiguous_matches"))]
+    pub max_ambiguous_matches: usize,
+}
+
+const DEFAULT_MAX_AMBIGUOUS_MATCHES: usize = 10;
+
+/// The fewest hash characters an abbreviation uses: the shortest hash the
+/// generator produces. Shorter ones would break as soon as a few more IDs exist.
+const MIN_ABBREVIATION_LEN: usize = 3;
+
+#[cfg(feature = "serde")]
+const fn default_max_ambiguous_matches() -> usize {
+    DEFAULT_MAX_AMBIGUOUS_MATCHES
 }
 
 impl ResolverConfig {
@@ -19,23 +54,127 @@ impl ResolverConfig {
             default_prefix: default_prefix.into(),
             allowed_prefixes: vec![],
             allow_substring_match: true,
+            try_allowed_prefixes: false,
+            normalizer: InputNormalizer::new(),
+            fuzzy: None,
+            max_ambiguous_matches: DEFAULT_MAX_AMBIGUOUS_MATCHES,
+        }
+    }
+
+    /// Returns true if `prefix` is the default prefix or one of the allowed prefixes.
+    #[must_use]
+    pub fn is_allowed_prefix(&self, prefix: &str) -> bool {
+        self.default_prefix == prefix || self.allowed_prefixes.iter().any(|p| p == prefix)
+    }
+
+    /// Returns true if `id` parses and its prefix is not allowed. IDs that do
+    /// not parse have no prefix to reject.
+    pub(crate) fn has_disallowed_prefix(&self, id: &str) -> bool {
+        parse_id(id).is_ok_and(|parsed| !self.is_allowed_prefix(&parsed.prefix))
+    }
+}
+
+/// Limits for the typo-tolerant suggestions made when resolution fails.
+///
+/// See [`find_similar_ids`] for how candidates are compared.
+///
+/// # Cost
+///
+/// Every input that resolves to nothing calls `substring_match_fn("")` to get
+/// the candidates, which for [`find_matching_ids`] or
+/// [`IdIndex::find_matching`] is every ID. Callers backed by a database should
+/// either leave [`ResolverConfig::fuzzy`] unset or answer the empty query with
+/// a bounded set, such as recent IDs or a capped count; suggestions are only
+/// drawn from what it returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzyConfig {
+    /// Maximum number of edits between the input hash and a suggested hash.
+    pub max_distance: usize,
+    /// Maximum number of suggestions to return.
+    pub max_suggestions: usize,
+    /// Input hash characters needed per allowed edit, so that short inputs
+    /// allow fewer edits. 0 always allows `max_distance`.
+    pub chars_per_edit: usize,
+}
+
+impl FuzzyConfig {
+    /// Creates a configuration allowing two edits, at most one per three
+    /// input characters, and three suggestions.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_distance: 2,
+            max_suggestions: 3,
+            chars_per_edit: 3,
+        }
+    }
+
+    /// Sets the maximum number of edits between input and suggestion.
+    #[must_use]
+    pub const fn max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Sets the maximum number of suggestions returned.
+    #[must_use]
+    pub const fn max_suggestions(mut self, max_suggestions: usize) -> Self {
+        self.max_suggestions = max_suggestions;
+        self
+    }
+
+    /// Sets how many input hash characters each allowed edit needs.
+    #[must_use]
+    pub const fn chars_per_edit(mut self, chars_per_edit: usize) -> Self {
+        self.chars_per_edit = chars_per_edit;
+        self
+    }
+
+    /// Returns the edits allowed for an input hash of `hash_len` characters:
+    /// `max_distance`, capped at one per `chars_per_edit` characters.
+    #[must_use]
+    pub const fn max_distance_for(&self, hash_len: usize) -> usize {
+        if self.chars_per_edit == 0 {
+            return self.max_distance;
         }
+        let scaled = hash_len / self.chars_per_edit;
+        if scaled < self.max_distance {
+            scaled
+        } else {
+            self.max_distance
+        }
+    }
+}
+
+impl Default for FuzzyConfig {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// The type of match found during ID resolution.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MatchType {
     /// Exact match of the input ID.
     Exact,
     /// Match after prefix normalization (prepending default prefix).
     PrefixNormalized,
+    /// Match via the hash starting with the input (git-style short ID).
+    HashPrefix,
     /// Match via substring search on hash portion.
     Substring,
+    /// Match by a custom [`ResolutionStage`], identified by its name.
+    Custom(String),
+    /// Picked from ambiguous candidates by the chooser passed to
+    /// [`IdResolver::resolve_or_choose`].
+    Chosen,
 }
 
 /// A resolved ID with match information.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ResolvedId {
     /// The resolved ID string.
     pub id: String,
@@ -43,32 +182,136 @@ pub struct ResolvedId {
     pub match_type: MatchType,
     /// The original input string.
     pub original_input: String,
+    /// Changes the normalizer made to the input before it matched, in order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub normalizations: Vec<Normalization>,
+}
+
+/// The results of [`IdResolver::resolve_many`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchResolution {
+    /// One result per input, in input order.
+    pub results: Vec<Result<ResolvedId>>,
+    /// IDs that more than one input resolved to, with the positions of those
+    /// inputs, in order of first occurrence.
+    pub duplicates: Vec<(String, Vec<usize>)>,
+}
+
+/// A source of IDs that answers resolver lookups in batches.
+///
+/// Implement this for a database so [`IdResolver::resolve_many`] can make one
+/// query per resolution stage instead of one per input.
+pub trait IdStore {
+    /// Returns whether each of `ids` exists, in the same order.
+    fn exists_many(&self, ids: &[&str]) -> Vec<bool>;
+
+    /// Returns, for each of `hash_substrings`, the IDs whose hash contains it,
+    /// like [`find_matching_ids`]. An empty substring matches every ID.
+    fn find_matching_many(&self, hash_substrings: &[&str]) -> Vec<Vec<String>>;
+}
+
+impl IdStore for IdIndex {
+    fn exists_many(&self, ids: &[&str]) -> Vec<bool> {
+        ids.iter().map(|id| self.contains(id)).collect()
+    }
+
+    fn find_matching_many(&self, hash_substrings: &[&str]) -> Vec<Vec<String>> {
+        hash_substrings
+            .iter()
+            .map(|substring| self.find_matching(substring))
+            .collect()
+    }
 }
 
 /// Resolver for fuzzy ID matching.
 pub struct IdResolver {
     config: ResolverConfig,
+    /// Custom pipeline; `None` runs the built-in stages.
+    stages: Option<Vec<Box<dyn ResolutionStage>>>,
 }
 
 impl IdResolver {
-    /// Creates a new ID resolver with the given configuration.
+    /// Creates a new ID resolver with the given configuration and the
+    /// built-in stages.
     #[must_use]
     pub const fn new(config: ResolverConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            stages: None,
+        }
+    }
+
+    /// Replaces the resolution pipeline.
+    ///
+    /// Stages run in order until one matches or fails. Start from
+    /// [`default_stages`](crate::stage::default_stages) to add custom stages around the built-in ones, or
+    /// reorder or drop them.
+    #[must_use]
+    pub fn stages(mut self, stages: Vec<Box<dyn ResolutionStage>>) -> Self {
+        self.stages = Some(stages);
+        self
+    }
+
+    /// Returns the names of the stages in the pipeline, in order.
+    pub fn stage_names(&self) -> impl Iterator<Item = &str> {
+        self.pipeline().map(ResolutionStage::name)
+    }
+
+    /// The stages to run, in order.
+    fn pipeline(&self) -> impl Iterator<Item = &dyn ResolutionStage> {
+        let custom = self.stages.iter().flatten().map(|stage| &**stage);
+        let built_in = BUILT_IN_STAGES
+            .iter()
+            .copied()
+            .filter(|_| self.stages.is_none());
+        custom.chain(built_in)
     }
 
     /// Resolves a user input to an ID using fuzzy matching.
     ///
-    /// Resolution order:
-    /// 1. Exact match — input (lowercased, trimmed) matches via `exists_fn`
-    /// 2. Prefix normalization — if no dash in input, prepend `default_prefix` + "-" and retry `exists_fn`
-    /// 3. Substring match — call `substring_match_fn` with input, exactly one match succeeds,
-    ///    multiple matches -> `AmbiguousId` error
-    /// 4. Not found -> `NotFound` error
+    /// The input is first cleaned up by the configured [`InputNormalizer`];
+    /// every change it makes is listed in [`ResolvedId::normalizations`].
+    ///
+    /// The input then goes through the pipeline of [`stages`](Self::stages).
+    /// The built-in order is:
+    /// 1. Exact match — normalized input matches via `exists_fn`
+    /// 2. Prefix normalization — if no dash in input, prepend `default_prefix` + "-" and retry
+    ///    `exists_fn`; with `try_allowed_prefixes`, then each allowed prefix in turn
+    /// 3. Hash prefix match — among the IDs from `substring_match_fn`, those whose hash
+    ///    starts with the input; exactly one succeeds, multiple -> `AmbiguousId` error
+    /// 4. Substring match — the remaining IDs from `substring_match_fn`, exactly one match
+    ///    succeeds, multiple matches -> `AmbiguousId` error
+    /// 5. Not found -> `NotFound` error
+    ///
+    /// No stage, built-in or custom, returns an ID whose prefix is outside
+    /// `default_prefix` and `allowed_prefixes`; such matches are ignored.
+    ///
+    /// Stages 3 and 4 only consider IDs as deep as the input, so `a7x` picks
+    /// `bd-a7x3q9` even when `bd-a7x3q9.1` exists.
+    ///
+    /// Input with a dash, such as `bd-a7`, is split at the last dash: stages 3
+    /// and 4 search for the part after it (`a7`) among IDs with the part before
+    /// it as their prefix.
+    ///
+    /// Input with a child path, such as `a7x.2`, that no stage matches as a
+    /// whole has its root part resolved by the stages, against the roots of
+    /// the candidates. The child path is then reattached and must exist;
+    /// if it doesn't, the `NotFound` error names the full child ID.
+    ///
+    /// If nothing is found and look-alike mapping is enabled, the stages are
+    /// retried once with `o`/`i`/`l` in the hash mapped to `0`/`1`/`1`.
+    ///
+    /// If nothing is found and [`ResolverConfig::fuzzy`] is set, the IDs from
+    /// `substring_match_fn("")` (every ID, for [`find_matching_ids`]) are ranked
+    /// with [`find_similar_ids`] and returned as suggestions. Suggestions are
+    /// never selected automatically.
     ///
     /// # Errors
     ///
+    /// Returns `PrefixMismatch` if the input is a full ID with a prefix that is
+    /// not allowed and no custom stage matched it.
     /// Returns `AmbiguousId` if multiple IDs match the substring.
+    /// Returns `NotFoundWithSuggestions` if no match is found but fuzzy matching found similar IDs.
     /// Returns `NotFound` if no match is found at any stage.
     pub fn resolve<F, G>(
         &self,
@@ -80,56 +323,536 @@ impl IdResolver {
         F: Fn(&str) -> bool,
         G: Fn(&str) -> Vec<String>,
     {
-        let original_input = input.to_string();
-        let normalized = input.to_lowercase().trim().to_string();
-
-        // Stage 1: Try exact match
-        if exists_fn(&normalized) {
-            return Ok(ResolvedId {
-                id: normalized,
-                match_type: MatchType::Exact,
-                original_input,
-            });
-        }
+        let (_, normalizations, result) =
+            self.resolve_with_steps(input, &exists_fn, &substring_match_fn);
+        let (id, match_type) = result?;
+        Ok(ResolvedId {
+            id,
+            match_type,
+            original_input: input.to_string(),
+            normalizations,
+        })
+    }
+
+    /// Resolves a user input against an [`IdIndex`].
+    ///
+    /// Same as [`resolve`](Self::resolve) with the index answering both lookups.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use terseid::{IdIndex, IdResolver, MatchType, ResolverConfig};
+    ///
+    /// let index: IdIndex = ["bd-a7x3q9", "bd-k2m4p1"].into_iter().collect();
+    /// let resolved = IdResolver::new(ResolverConfig::new("bd"))
+    ///     .resolve_indexed("a7x", &index)
+    ///     .unwrap();
+    /// assert_eq!(resolved.id, "bd-a7x3q9");
+    /// assert_eq!(resolved.match_type, MatchType::HashPrefix);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Same as [`resolve`](Self::resolve).
+    pub fn resolve_indexed(&self, input: &str, index: &IdIndex) -> Result<ResolvedId> {
+        self.resolve(
+            input,
+            |id| index.contains(id),
+            |substring| index.find_matching(substring),
+        )
+    }
 
-        // Stage 2: Try prefix normalization (if no dash in input)
-        if !normalized.contains('-') {
-            let prefixed = format!("{}-{}", self.config.default_prefix, normalized);
-            if exists_fn(&prefixed) {
+    /// Resolves a user input, letting `choose` pick when it is ambiguous.
+    ///
+    /// Works like [`resolve`](Self::resolve), except that on an `AmbiguousId`
+    /// error `choose` is called with the partial input, the ranked (and
+    /// possibly capped) candidates and the total number of candidates. It
+    /// returns the index of the chosen candidate, or `None` to give up. For
+    /// input with a child path whose root was ambiguous, such as `a7x.2`, the
+    /// child path is reattached to the chosen root.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use terseid::{IdIndex, IdResolver, MatchType, ResolverConfig};
+    ///
+    /// let index: IdIndex = ["bd-a7x3q9", "bd-a7x4k2"].into_iter().collect();
+    /// let resolver = IdResolver::new(ResolverConfig::new("bd"));
+    /// let resolved = resolver
+    ///     .resolve_or_choose(
+    ///         "a7x",
+    ///         |id| index.contains(id),
+    ///         |substring| index.find_matching(substring),
+    ///         |_partial, candidates, _total| candidates.iter().position(|id| id.ends_with("k2")),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(resolved.id, "bd-a7x4k2");
+    /// assert_eq!(resolved.match_type, MatchType::Chosen);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Same as [`resolve`](Self::resolve). `AmbiguousId` is returned unchanged
+    /// if `choose` returns `None` or an index out of range, and `NotFound` if
+    /// the chosen root has no such child.
+    pub fn resolve_or_choose<F, G, C>(
+        &self,
+        input: &str,
+        exists_fn: F,
+        substring_match_fn: G,
+        choose: C,
+    ) -> Result<ResolvedId>
+    where
+        F: Fn(&str) -> bool,
+        G: Fn(&str) -> Vec<String>,
+        C: FnOnce(&str, &[String], usize) -> Option<usize>,
+    {
+        let (normalized, normalizations, result) =
+            self.resolve_with_steps(input, &exists_fn, &substring_match_fn);
+        let (partial, matches, total) = match result {
+            Ok((id, match_type)) => {
                 return Ok(ResolvedId {
-                    id: prefixed,
-                    match_type: MatchType::PrefixNormalized,
-                    original_input,
+                    id,
+                    match_type,
+                    original_input: input.to_string(),
+                    normalizations,
                 });
             }
-        }
+            Err(TerseIdError::AmbiguousId {
+                partial,
+                matches,
+                total,
+            }) => (partial, matches, total),
+            Err(err) => return Err(err),
+        };
+        let Some(chosen) = choose(&partial, &matches, total).and_then(|i| matches.get(i)) else {
+            return Err(TerseIdError::AmbiguousId {
+                partial,
+                matches,
+                total,
+            });
+        };
 
-        // Stage 3: Try substring match
-        if self.config.allow_substring_match {
-            let matches = substring_match_fn(&normalized);
-            match matches.len() {
-                0 => {
-                    // Fall through to not found
+        let id = match child_path_start(&normalized) {
+            Some(dot) if child_path_start(&partial).is_none() => {
+                let id = format!("{chosen}{}", &normalized[dot..]);
+                if !exists_fn(&id) {
+                    return Err(TerseIdError::NotFound { id });
                 }
-                1 => {
-                    return Ok(ResolvedId {
-                        id: matches[0].clone(),
-                        match_type: MatchType::Substring,
-                        original_input,
-                    });
+                id
+            }
+            _ => chosen.clone(),
+        };
+        Ok(ResolvedId {
+            id,
+            match_type: MatchType::Chosen,
+            original_input: input.to_string(),
+            normalizations,
+        })
+    }
+
+    /// Resolves several inputs at once, batching the lookups against `store`.
+    ///
+    /// Identical inputs are resolved once. Resolution runs in rounds: every
+    /// pending input runs the stages of [`resolve`](Self::resolve), treating
+    /// lookups that haven't been made yet as misses, and all such lookups are
+    /// then sent to `store` in one `exists_many` and one `find_matching_many`
+    /// call. An input is done once a round needs no new lookups. The number of
+    /// rounds depends on how many stages depend on earlier answers, not on the
+    /// number of inputs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use terseid::{IdIndex, IdResolver, ResolverConfig};
+    ///
+    /// let index: IdIndex = ["bd-a7x3q9", "bd-k2m4p1"].into_iter().collect();
+    /// let batch = IdResolver::new(ResolverConfig::new("bd"))
+    ///     .resolve_many(&["a7x", "k2m", "zzz", "bd-a7x3q9"], &index);
+    ///
+    /// assert_eq!(batch.results[1].as_ref().unwrap().id, "bd-k2m4p1");
+    /// assert!(batch.results[2].is_err());
+    /// assert_eq!(batch.duplicates, [("bd-a7x3q9".to_string(), vec![0, 3])]);
+    /// ```
+    pub fn resolve_many<S>(&self, inputs: &[impl AsRef<str>], store: &S) -> BatchResolution
+    where
+        S: IdStore + ?Sized,
+    {
+        let mut unique: Vec<&str> = Vec::new();
+        let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+        let positions: Vec<usize> = inputs
+            .iter()
+            .map(|input| {
+                let input = input.as_ref();
+                *seen.entry(input).or_insert_with(|| {
+                    unique.push(input);
+                    unique.len() - 1
+                })
+            })
+            .collect();
+
+        let mut exists: BTreeMap<String, bool> = BTreeMap::new();
+        let mut matches: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut resolved: Vec<Option<Result<ResolvedId>>> = vec![None; unique.len()];
+        loop {
+            // Lookups are answered from what earlier rounds fetched; a miss
+            // is queued and the input is retried next round.
+            let missing_ids = RefCell::new(BTreeSet::new());
+            let missing_substrings = RefCell::new(BTreeSet::new());
+            for (slot, input) in resolved.iter_mut().zip(&unique) {
+                if slot.is_some() {
+                    continue;
                 }
-                _ => {
-                    return Err(TerseIdError::AmbiguousId {
-                        partial: normalized,
-                        matches,
-                    });
+                let missed = Cell::new(false);
+                let result = self.resolve(
+                    input,
+                    |id| {
+                        exists.get(id).copied().unwrap_or_else(|| {
+                            missed.set(true);
+                            missing_ids.borrow_mut().insert(id.to_string());
+                            false
+                        })
+                    },
+                    |substring| {
+                        matches.get(substring).cloned().unwrap_or_else(|| {
+                            missed.set(true);
+                            missing_substrings
+                                .borrow_mut()
+                                .insert(substring.to_string());
+                            Vec::new()
+                        })
+                    },
+                );
+                if !missed.get() {
+                    *slot = Some(result);
                 }
             }
+
+            let missing_ids: Vec<String> = missing_ids.into_inner().into_iter().collect();
+            let missing_substrings: Vec<String> =
+                missing_substrings.into_inner().into_iter().collect();
+            if missing_ids.is_empty() && missing_substrings.is_empty() {
+                break;
+            }
+            if !missing_ids.is_empty() {
+                let batch: Vec<&str> = missing_ids.iter().map(String::as_str).collect();
+                let found = store.exists_many(&batch);
+                // Missing answers count as "no", so every queued lookup is settled.
+                let found = found.into_iter().chain(core::iter::repeat(false));
+                exists.extend(missing_ids.into_iter().zip(found));
+            }
+            if !missing_substrings.is_empty() {
+                let batch: Vec<&str> = missing_substrings.iter().map(String::as_str).collect();
+                let found = store.find_matching_many(&batch);
+                let found = found.into_iter().chain(core::iter::repeat_with(Vec::new));
+                matches.extend(missing_substrings.into_iter().zip(found));
+            }
         }
 
-        // Stage 4: Not found
+        let resolved: Vec<Result<ResolvedId>> = resolved.into_iter().flatten().collect();
+        let results: Vec<Result<ResolvedId>> = positions
+            .iter()
+            .map(|&position| resolved[position].clone())
+            .collect();
+
+        let mut duplicates: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut first_seen: BTreeMap<&str, usize> = BTreeMap::new();
+        for (position, result) in results.iter().enumerate() {
+            let Ok(found) = result else {
+                continue;
+            };
+            let entry = *first_seen.entry(&found.id).or_insert_with(|| {
+                duplicates.push((found.id.clone(), Vec::new()));
+                duplicates.len() - 1
+            });
+            duplicates[entry].1.push(position);
+        }
+        duplicates.retain(|(_, positions)| positions.len() > 1);
+
+        BatchResolution {
+            results,
+            duplicates,
+        }
+    }
+
+    /// Returns the shortest input that resolves back to `id` against `index`,
+    /// like git's abbreviated commit hashes.
+    ///
+    /// Candidates are leading parts of the hash of at least three characters
+    /// (or the whole hash if it is shorter), shortest first, with any child path
+    /// kept and with or without the prefix (`a7x.2`, `bd-a7x.2`). The first one
+    /// that [`resolve_indexed`](Self::resolve_indexed) maps back to `id` wins;
+    /// if none does, the full normalized ID is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use terseid::{IdIndex, IdResolver, ResolverConfig};
+    ///
+    /// let index: IdIndex = ["bd-a7x3q9", "bd-a7y2k1", "bd-k2m4p1"].into_iter().collect();
+    /// let resolver = IdResolver::new(ResolverConfig::new("bd"));
+    /// assert_eq!(resolver.shortest_unique("bd-a7x3q9", &index).unwrap(), "a7x");
+    /// assert_eq!(resolver.shortest_unique("bd-k2m4p1", &index).unwrap(), "k2m");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidId` if `id` cannot be parsed, `PrefixMismatch` if its
+    /// prefix is not allowed, and `NotFound` if it is not in `index`.
+    pub fn shortest_unique(&self, id: &str, index: &IdIndex) -> Result<String> {
+        self.abbreviate(id, index, MIN_ABBREVIATION_LEN)
+    }
+
+    /// Abbreviates every ID in `ids` against the others, using at least
+    /// `min_len` hash characters (like `git log --abbrev=<n>`). A `min_len`
+    /// below three is raised to three, as in
+    /// [`shortest_unique`](Self::shortest_unique).
+    ///
+    /// Returns one entry per input, in order. Each resolves back to its ID
+    /// through this resolver; inputs that are invalid or have a prefix that is
+    /// not allowed are returned unchanged.
+    #[must_use]
+    pub fn abbreviate_all(&self, ids: &[impl AsRef<str>], min_len: usize) -> Vec<String> {
+        let index = IdIndex::from_ids(ids);
+        ids.iter()
+            .map(|id| {
+                self.abbreviate(id.as_ref(), &index, min_len)
+                    .unwrap_or_else(|_| id.as_ref().to_string())
+            })
+            .collect()
+    }
+
+    fn abbreviate(&self, id: &str, index: &IdIndex, min_len: usize) -> Result<String> {
+        let parsed = parse_id(id)?;
+        if !self.config.is_allowed_prefix(&parsed.prefix) {
+            return Err(TerseIdError::PrefixMismatch {
+                expected: self.config.default_prefix.clone(),
+                found: parsed.prefix,
+            });
+        }
+        let full = parsed.to_id_string();
+        if !index.contains(&full) {
+            return Err(TerseIdError::NotFound { id: full });
+        }
+
+        let path = &full[parsed.prefix.len() + 1 + parsed.hash.len()..];
+        let hash_chars = parsed.hash.chars().count();
+        let mut candidates: Vec<String> = parsed
+            .hash
+            .char_indices()
+            .map(|(end, _)| end)
+            .skip(1)
+            .chain(core::iter::once(parsed.hash.len()))
+            .skip(min_len.max(MIN_ABBREVIATION_LEN).min(hash_chars) - 1)
+            .flat_map(|end| {
+                let short = &parsed.hash[..end];
+                [
+                    format!("{short}{path}"),
+                    format!("{}-{short}{path}", parsed.prefix),
+                ]
+            })
+            .collect();
+        candidates.sort_by_key(String::len);
+
+        Ok(candidates
+            .into_iter()
+            .find(|candidate| {
+                self.resolve_indexed(candidate, index)
+                    .is_ok_and(|resolved| resolved.id == full)
+            })
+            .unwrap_or(full))
+    }
+
+    /// Normalizes `input` and resolves it, retrying with look-alikes mapped if
+    /// nothing matched. Returns the normalized input the result came from and
+    /// the normalization steps that produced it, for both success and failure.
+    fn resolve_with_steps<F, G>(
+        &self,
+        input: &str,
+        exists_fn: &F,
+        substring_match_fn: &G,
+    ) -> (String, Vec<Normalization>, Result<(String, MatchType)>)
+    where
+        F: Fn(&str) -> bool,
+        G: Fn(&str) -> Vec<String>,
+    {
+        let (normalized, mut normalizations) = self.config.normalizer.normalize(input);
+        let result =
+            match self.resolve_normalized(normalized.clone(), exists_fn, substring_match_fn) {
+                Err(TerseIdError::NotFound { id }) => {
+                    let not_found = || Err(self.not_found(id.clone(), substring_match_fn));
+                    let Some(mapped) = self.config.normalizer.map_lookalikes(&normalized) else {
+                        return (normalized, normalizations, not_found());
+                    };
+                    match self.resolve_normalized(mapped.clone(), exists_fn, substring_match_fn) {
+                        Err(TerseIdError::NotFound { .. }) => not_found(),
+                        result => {
+                            normalizations.push(Normalization::Lookalikes {
+                                from: normalized,
+                                to: mapped.clone(),
+                            });
+                            return (mapped, normalizations, result);
+                        }
+                    }
+                }
+                other => other,
+            };
+        (normalized, normalizations, result)
+    }
+
+    /// Runs the resolution stages on already-normalized input.
+    fn resolve_normalized<F, G>(
+        &self,
+        normalized: String,
+        exists_fn: &F,
+        substring_match_fn: &G,
+    ) -> Result<(String, MatchType)>
+    where
+        F: Fn(&str) -> bool,
+        G: Fn(&str) -> Vec<String>,
+    {
+        // Input with an explicit prefix outside the allowed set can only
+        // resolve through a custom stage, e.g. an alias containing a dash
+        let disallowed_prefix = parse_id(&normalized)
+            .ok()
+            .map(|parsed| parsed.prefix)
+            .filter(|prefix| !self.config.is_allowed_prefix(prefix));
+        match (
+            self.resolve_child_path(normalized, exists_fn, substring_match_fn),
+            disallowed_prefix,
+        ) {
+            (Err(TerseIdError::NotFound { .. }), Some(found)) => {
+                Err(TerseIdError::PrefixMismatch {
+                    expected: self.config.default_prefix.clone(),
+                    found,
+                })
+            }
+            (other, _) => other,
+        }
+    }
+
+    /// Runs the pipeline on the input, and for input with a child path, on its root.
+    fn resolve_child_path<F, G>(
+        &self,
+        normalized: String,
+        exists_fn: &F,
+        substring_match_fn: &G,
+    ) -> Result<(String, MatchType)>
+    where
+        F: Fn(&str) -> bool,
+        G: Fn(&str) -> Vec<String>,
+    {
+        let Some(dot) = child_path_start(&normalized) else {
+            return self.run_stages(normalized, exists_fn, substring_match_fn);
+        };
+
+        // The whole input may still name an ID directly
+        match self.run_stages(normalized.clone(), exists_fn, substring_match_fn) {
+            Err(TerseIdError::NotFound { .. }) => {}
+            other => return other,
+        }
+
+        // Otherwise resolve the root among the roots of the candidates, then
+        // check that the child exists under it
+        let (root, path) = normalized.split_at(dot);
+        let roots_fn = |sub: &str| roots_of(substring_match_fn(sub));
+        let (root_id, match_type) = match self.run_stages(root.to_string(), exists_fn, &roots_fn) {
+            Err(TerseIdError::NotFound { .. }) => {
+                return Err(TerseIdError::NotFound { id: normalized });
+            }
+            other => other?,
+        };
+        let id = format!("{root_id}{path}");
+        if exists_fn(&id) {
+            Ok((id, match_type))
+        } else {
+            Err(TerseIdError::NotFound { id })
+        }
+    }
+
+    /// Runs the pipeline until a stage matches or fails. Matches with a prefix
+    /// that is not allowed are skipped.
+    fn run_stages(
+        &self,
+        normalized: String,
+        exists_fn: &dyn Fn(&str) -> bool,
+        substring_match_fn: &dyn Fn(&str) -> Vec<String>,
+    ) -> Result<(String, MatchType)> {
+        let context = StageContext::new(&normalized, &self.config, exists_fn, substring_match_fn);
+        for stage in self.pipeline() {
+            if let Some(id) = stage.resolve(&context)?
+                && !self.config.has_disallowed_prefix(&id)
+            {
+                return Ok((id, stage.match_type()));
+            }
+        }
         Err(TerseIdError::NotFound { id: normalized })
     }
+
+    /// The error for input that matched nothing, with suggestions if fuzzy matching is on.
+    fn not_found<G>(&self, id: String, substring_match_fn: &G) -> TerseIdError
+    where
+        G: Fn(&str) -> Vec<String>,
+    {
+        let Some(fuzzy) = self.config.fuzzy else {
+            return TerseIdError::NotFound { id };
+        };
+        let mut candidates = substring_match_fn("");
+        candidates.retain(|candidate| !self.config.has_disallowed_prefix(candidate));
+        let hash_start = id.rfind('-').map_or(0, |dash| dash + 1);
+        let hash = id[hash_start..].split('.').next().unwrap_or_default();
+        let max_distance = fuzzy.max_distance_for(hash.chars().count());
+        let mut suggestions = find_similar_ids(&candidates, &id, max_distance);
+        suggestions.truncate(fuzzy.max_suggestions);
+        if suggestions.is_empty() {
+            TerseIdError::NotFound { id }
+        } else {
+            TerseIdError::NotFoundWithSuggestions { id, suggestions }
+        }
+    }
+}
+
+/// Byte offset of the `.` that starts a child path such as `.2.1`, if the
+/// input has a non-empty hash followed by a well-formed child path.
+fn child_path_start(input: &str) -> Option<usize> {
+    let hash_start = input.rfind('-').map_or(0, |dash| dash + 1);
+    let dot = hash_start + input[hash_start..].find('.')?;
+    let well_formed = dot > hash_start
+        && input[dot + 1..]
+            .split('.')
+            .all(|segment| !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()));
+    well_formed.then_some(dot)
+}
+
+/// The distinct root IDs of `ids`, in first-seen order. Invalid IDs are dropped.
+fn roots_of(ids: Vec<String>) -> Vec<String> {
+    let mut roots: Vec<String> = Vec::new();
+    for id in ids {
+        let Ok(mut parsed) = parse_id(&id) else {
+            continue;
+        };
+        parsed.child_path.clear();
+        let root = parsed.to_id_string();
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// Finds IDs whose hash starts with the given prefix.
+///
+/// This is the git-style short ID lookup: `a7x` finds `bd-a7x3q9` but not
+/// `bd-qa7x`. Both the IDs and the prefix are compared case-insensitively, and
+/// results are normalized.
+pub fn find_ids_by_hash_prefix(all_ids: &[impl AsRef<str>], hash_prefix: &str) -> Vec<String> {
+    let needle = hash_prefix.to_lowercase();
+    all_ids
+        .iter()
+        .filter_map(|id| parse_id(id.as_ref()).ok())
+        .filter(|parsed| parsed.hash.starts_with(&needle))
+        .map(|parsed| parsed.to_id_string())
+        .collect()
 }
 
 /// Finds IDs matching a hash substring.
@@ -154,6 +877,93 @@ pub fn find_matching_ids(all_ids: &[impl AsRef<str>], hash_substring: &str) -> V
         .collect()
 }
 
+/// Finds IDs whose hash is within `max_distance` edits of the input's hash.
+///
+/// An edit is an insertion, deletion, substitution or swap of two adjacent
+/// characters (Damerau-Levenshtein distance in its optimal string alignment
+/// form). The input may be a full ID or a bare hash, with or without a child
+/// path; the child path, and the prefix if one is given, must match exactly.
+/// Hashes longer than the input are also compared by their leading characters,
+/// so a mistyped short ID still finds its match. `max_distance` is used as
+/// given; [`FuzzyConfig::max_distance_for`] scales it down for short inputs.
+///
+/// Results are normalized and ranked by distance, then in natural order.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::find_similar_ids;
+///
+/// let all_ids = ["bd-a7x3q9", "bd-k2m4p1", "bd-a7x3q9.1"];
+/// assert_eq!(find_similar_ids(&all_ids, "a7x3g9", 2), ["bd-a7x3q9"]);
+/// assert_eq!(find_similar_ids(&all_ids, "bd-7ax3q9.1", 2), ["bd-a7x3q9.1"]);
+/// ```
+pub fn find_similar_ids(
+    all_ids: &[impl AsRef<str>],
+    input: &str,
+    max_distance: usize,
+) -> Vec<String> {
+    let input = input.to_lowercase();
+    let (prefix, target) = if input.contains('-') {
+        let Ok(parsed) = parse_id(&input) else {
+            return Vec::new();
+        };
+        (Some(parsed.prefix.clone()), parsed)
+    } else {
+        // Borrow a prefix so the hash and child path parse as usual.
+        let Ok(parsed) = parse_id(&format!("x-{input}")) else {
+            return Vec::new();
+        };
+        (None, parsed)
+    };
+    let needle: Vec<char> = target.hash.chars().collect();
+
+    let mut ranked: Vec<(usize, ParsedId)> = all_ids
+        .iter()
+        .filter_map(|id| parse_id(id.as_ref()).ok())
+        .filter(|candidate| {
+            candidate.child_path == target.child_path
+                && prefix.as_ref().is_none_or(|p| *p == candidate.prefix)
+        })
+        .filter_map(|candidate| {
+            let hash: Vec<char> = candidate.hash.chars().collect();
+            let mut distance = edit_distance(&needle, &hash);
+            if hash.len() > needle.len() {
+                distance = distance.min(edit_distance(&needle, &hash[..needle.len()]));
+            }
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    ranked.sort_unstable();
+    ranked.dedup();
+    ranked
+        .into_iter()
+        .map(|(_, id)| id.to_id_string())
+        .collect()
+}
+
+/// Optimal string alignment distance between `a` and `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut before_prev = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (prev[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_prev[j - 2] + 1);
+            }
+        }
+        core::mem::swap(&mut before_prev, &mut prev);
+        core::mem::swap(&mut prev, &mut current);
+    }
+    prev[b.len()]
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::similar_names)]
 mod tests {
@@ -182,6 +992,7 @@ mod tests {
             id: "bd-a7x".to_string(),
             match_type: MatchType::Exact,
             original_input: "BD-A7X".to_string(),
+            normalizations: vec![Normalization::Lowercased],
         };
         assert_eq!(id.id, "bd-a7x");
         assert_eq!(id.match_type, MatchType::Exact);
@@ -286,12 +1097,12 @@ mod tests {
 
         let substring_fn = |_: &str| vec!["bd-a7x".to_string()];
 
-        let result = resolver.resolve("a7", |id| id == "nonexistent", substring_fn);
+        let result = resolver.resolve("7x", |id| id == "nonexistent", substring_fn);
         assert!(result.is_ok());
         let resolved = result.unwrap();
         assert_eq!(resolved.id, "bd-a7x");
         assert_eq!(resolved.match_type, MatchType::Substring);
-        assert_eq!(resolved.original_input, "a7");
+        assert_eq!(resolved.original_input, "7x");
     }
 
     #[test]
@@ -304,9 +1115,14 @@ mod tests {
         let result = resolver.resolve("a7", |id| id == "nonexistent", substring_fn);
         assert!(result.is_err());
         match result.unwrap_err() {
-            TerseIdError::AmbiguousId { partial, matches } => {
+            TerseIdError::AmbiguousId {
+                partial,
+                matches,
+                total,
+            } => {
                 assert_eq!(partial, "a7");
                 assert_eq!(matches.len(), 2);
+                assert_eq!(total, 2);
             }
             _ => panic!("Expected AmbiguousId error"),
         }
@@ -391,6 +1207,103 @@ mod tests {
         assert_eq!(resolved.match_type, MatchType::PrefixNormalized);
     }
 
+    // ========== Hash prefix tests ==========
+
+    #[test]
+    fn test_resolve_hash_prefix_beats_substring() {
+        // "a7x" is in the middle of bd-qa7x, but only bd-a7x3q9 starts with it.
+        let ids = known(&["bd-a7x3q9", "bd-qa7x1"]);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "a7x").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+    }
+
+    #[test]
+    fn test_resolve_hash_prefix_ambiguous_lists_only_prefix_matches() {
+        let ids = known(&["bd-a7x3q9", "bd-a7x4k2", "bd-qa7x1"]);
+        let err = resolve_in(ResolverConfig::new("bd"), &ids, "a7x").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: vec!["bd-a7x3q9".to_string(), "bd-a7x4k2".to_string()],
+                total: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_substring_considers_leftovers() {
+        let ids = known(&["bd-qa7x1", "bd-za7x2"]);
+        let err = resolve_in(ResolverConfig::new("bd"), &ids, "a7x").unwrap_err();
+        assert!(matches!(err, TerseIdError::AmbiguousId { .. }));
+
+        let ids = known(&["bd-qa7x1", "bd-k2m"]);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "a7x").unwrap();
+        assert_eq!(resolved.match_type, MatchType::Substring);
+    }
+
+    #[test]
+    fn test_substring_matches_queried_once_per_input() {
+        let ids = family();
+        let calls = Cell::new(0);
+        let resolve_counted = |input: &str| {
+            calls.set(0);
+            let resolved = IdResolver::new(ResolverConfig::new("bd")).resolve(
+                input,
+                |id| ids.iter().any(|k| k == id),
+                |sub| {
+                    calls.set(calls.get() + 1);
+                    find_matching_ids(&ids, sub)
+                },
+            );
+            (resolved.unwrap().id, calls.get())
+        };
+
+        assert_eq!(resolve_counted("x3q"), ("bd-a7x3q9".to_string(), 1));
+        assert_eq!(resolve_counted("a7x"), ("bd-a7x3q9".to_string(), 1));
+        // Once for `x3q.1` as a whole, once for its root part.
+        assert_eq!(resolve_counted("x3q.1"), ("bd-a7x3q9.1".to_string(), 2));
+    }
+
+    #[test]
+    fn test_find_ids_by_hash_prefix() {
+        let all_ids = vec!["bd-a7x3q9", "BD-A7Y", "bd-qa7x", "tk-a7x.1", "invalid"];
+        assert_eq!(
+            find_ids_by_hash_prefix(&all_ids, "A7"),
+            vec!["bd-a7x3q9", "bd-a7y", "tk-a7x.1"]
+        );
+        assert!(find_ids_by_hash_prefix(&all_ids, "x3").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_partial_root_with_children() {
+        let ids = known(&[
+            "bd-a7x3q9",
+            "bd-a7x3q9.1",
+            "bd-a7x3q9.2",
+            "bd-qa7x1",
+            "bd-qa7x1.1",
+        ]);
+        let config = ResolverConfig::new("bd");
+
+        let resolved = resolve_in(config.clone(), &ids, "a7x").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+        assert_eq!(
+            resolve_in(config.clone(), &ids, "bd-a7").unwrap().id,
+            "bd-a7x3q9"
+        );
+
+        let resolved = resolve_in(config.clone(), &ids, "qa7").unwrap();
+        assert_eq!(resolved.id, "bd-qa7x1");
+        let resolved = resolve_in(config.clone(), &ids, "7x1").unwrap();
+        assert_eq!(resolved.id, "bd-qa7x1");
+        assert_eq!(resolved.match_type, MatchType::Substring);
+
+        assert_eq!(resolve_in(config, &ids, "a7x.2").unwrap().id, "bd-a7x3q9.2");
+    }
+
     // ========== find_matching_ids tests ==========
 
     #[test]
@@ -474,19 +1387,1045 @@ mod tests {
         let result = resolver.resolve("a7x", exists_fn, substring_fn);
         assert_eq!(result.unwrap().match_type, MatchType::PrefixNormalized);
 
-        // Test 3: substring match
+        // Test 3: hash prefix match
         let result = resolver.resolve("a7", exists_fn, substring_fn);
+        assert_eq!(result.unwrap().match_type, MatchType::HashPrefix);
+
+        // Test 4: substring match
+        let result = resolver.resolve("7x", exists_fn, substring_fn);
         assert_eq!(result.unwrap().match_type, MatchType::Substring);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_resolver_config_serde_roundtrip() {
+        let mut config = ResolverConfig::new("bd");
+        config.allowed_prefixes = vec!["tk".to_string()];
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"default_prefix":"bd","allowed_prefixes":["tk"],"allow_substring_match":true,"try_allowed_prefixes":false,"normalizer":{"strip_noise":true,"url_path_segment":true,"lookalikes":false},"fuzzy":null,"max_ambiguous_matches":10}"#
+        );
+        let back: ResolverConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.default_prefix, "bd");
+        assert_eq!(back.allowed_prefixes, vec!["tk".to_string()]);
+        assert!(back.allow_substring_match);
+        assert_eq!(back.normalizer, InputNormalizer::new());
+
+        // Configs saved before the normalizer existed still load.
+        let old = r#"{"default_prefix":"bd","allowed_prefixes":[],"allow_substring_match":false}"#;
+        let back: ResolverConfig = serde_json::from_str(old).unwrap();
+        assert_eq!(back.normalizer, InputNormalizer::new());
+        assert!(back.fuzzy.is_none());
+        assert_eq!(back.max_ambiguous_matches, DEFAULT_MAX_AMBIGUOUS_MATCHES);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_resolved_id_serde_roundtrip() {
+        let resolved = ResolvedId {
+            id: "bd-a7x".to_string(),
+            match_type: MatchType::PrefixNormalized,
+            original_input: "A7X".to_string(),
+            normalizations: vec![Normalization::Lowercased],
+        };
+        let json = serde_json::to_string(&resolved).unwrap();
+        assert_eq!(
+            json,
+            r#"{"id":"bd-a7x","match_type":"PrefixNormalized","original_input":"A7X","normalizations":["Lowercased"]}"#
+        );
+        let back: ResolvedId = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, resolved);
+    }
+
     #[test]
     fn test_resolved_id_clone_and_equality() {
         let id1 = ResolvedId {
             id: "bd-a7x".to_string(),
             match_type: MatchType::Exact,
             original_input: "bd-a7x".to_string(),
+            normalizations: vec![],
         };
         let id2 = id1.clone();
         assert_eq!(id1, id2);
     }
+
+    // ========== Input normalization ==========
+
+    fn known(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(ToString::to_string).collect()
+    }
+
+    fn resolve_in(config: ResolverConfig, ids: &[String], input: &str) -> Result<ResolvedId> {
+        IdResolver::new(config).resolve(
+            input,
+            |id| ids.iter().any(|k| k == id),
+            |sub| find_matching_ids(ids, sub),
+        )
+    }
+
+    #[test]
+    fn test_resolve_records_normalizations() {
+        let ids = known(&["bd-a7x3q9"]);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, " (#BD-A7X3Q9), ").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert_eq!(resolved.match_type, MatchType::Exact);
+        assert_eq!(resolved.original_input, " (#BD-A7X3Q9), ");
+        assert_eq!(
+            resolved.normalizations,
+            vec![
+                Normalization::Trimmed,
+                Normalization::Lowercased,
+                Normalization::StrippedLeading {
+                    removed: "(#".to_string()
+                },
+                Normalization::StrippedTrailing {
+                    removed: "),".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let ids = known(&["bd-a7x3q9"]);
+        let resolved = resolve_in(
+            ResolverConfig::new("bd"),
+            &ids,
+            "https://tracker.example/issues/bd-a7x3q9?tab=activity",
+        )
+        .unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert!(matches!(
+            resolved.normalizations[..],
+            [Normalization::UrlPathSegment { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_resolve_short_form_with_noise() {
+        let ids = known(&["bd-a7x3q9"]);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "#a7x3q9.").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert_eq!(resolved.match_type, MatchType::PrefixNormalized);
+    }
+
+    #[test]
+    fn test_resolve_minimal_normalizer_keeps_noise() {
+        let ids = known(&["bd-a7x3q9"]);
+        let mut config = ResolverConfig::new("bd");
+        config.normalizer = InputNormalizer::minimal();
+        config.allow_substring_match = false;
+        // Without stripping, "#bd" is taken as the prefix.
+        assert_eq!(
+            resolve_in(config, &ids, "#bd-a7x3q9").unwrap_err(),
+            TerseIdError::PrefixMismatch {
+                expected: "bd".to_string(),
+                found: "#bd".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_lookalikes_fallback() {
+        let ids = known(&["bd-a70x1q"]);
+        let mut config = ResolverConfig::new("bd");
+        config.normalizer = InputNormalizer::new().lookalikes(true);
+
+        let resolved = resolve_in(config, &ids, "BD-A7OXLQ").unwrap();
+        assert_eq!(resolved.id, "bd-a70x1q");
+        assert_eq!(
+            resolved.normalizations.last(),
+            Some(&Normalization::Lookalikes {
+                from: "bd-a7oxlq".to_string(),
+                to: "bd-a70x1q".to_string(),
+            })
+        );
+
+        // Off by default.
+        assert!(resolve_in(ResolverConfig::new("bd"), &ids, "bd-a7oxlq").is_err());
+    }
+
+    #[test]
+    fn test_resolve_lookalikes_only_as_fallback() {
+        // "bd-oil" is a real ID; mapping must not turn it into "bd-011".
+        let ids = known(&["bd-oil", "bd-011"]);
+        let mut config = ResolverConfig::new("bd");
+        config.normalizer = InputNormalizer::new().lookalikes(true);
+        let resolved = resolve_in(config.clone(), &ids, "bd-oil").unwrap();
+        assert_eq!(resolved.id, "bd-oil");
+        assert!(resolved.normalizations.is_empty());
+
+        let err = resolve_in(config, &ids, "bd-zzo").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::NotFound {
+                id: "bd-zzo".to_string()
+            }
+        );
+    }
+
+    // ========== Allowed prefixes ==========
+
+    fn review_config() -> ResolverConfig {
+        let mut config = ResolverConfig::new("cr");
+        config.allowed_prefixes = vec!["th".to_string()];
+        config
+    }
+
+    #[test]
+    fn test_is_allowed_prefix() {
+        let config = review_config();
+        assert!(config.is_allowed_prefix("cr"));
+        assert!(config.is_allowed_prefix("th"));
+        assert!(!config.is_allowed_prefix("bd"));
+    }
+
+    #[test]
+    fn test_explicit_disallowed_prefix_is_mismatch() {
+        let ids = known(&["bd-a7x3q9", "cr-a7x3q9"]);
+        let err = resolve_in(review_config(), &ids, "BD-A7X3Q9").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::PrefixMismatch {
+                expected: "cr".to_string(),
+                found: "bd".to_string(),
+            }
+        );
+        // Also when the ID does not exist at all.
+        assert!(matches!(
+            resolve_in(review_config(), &ids, "bd-zzz"),
+            Err(TerseIdError::PrefixMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_allowed_prefix_exact_match() {
+        let ids = known(&["th-k2m"]);
+        let resolved = resolve_in(review_config(), &ids, "th-k2m").unwrap();
+        assert_eq!(resolved.id, "th-k2m");
+        assert_eq!(resolved.match_type, MatchType::Exact);
+    }
+
+    #[test]
+    fn test_substring_filters_other_namespaces() {
+        let ids = known(&["bd-a7x3q9", "cr-a7x3k1"]);
+        let resolved = resolve_in(review_config(), &ids, "a7x3").unwrap();
+        assert_eq!(resolved.id, "cr-a7x3k1");
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+
+        let only_other = known(&["bd-a7x3q9"]);
+        assert!(matches!(
+            resolve_in(review_config(), &only_other, "a7x3"),
+            Err(TerseIdError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ambiguity_only_counts_allowed_ids() {
+        let ids = known(&["cr-a7x1", "th-a7x2", "bd-a7x3"]);
+        let err = resolve_in(review_config(), &ids, "a7x").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: vec!["cr-a7x1".to_string(), "th-a7x2".to_string()],
+                total: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_try_allowed_prefixes() {
+        let ids = known(&["th-k2m"]);
+        let mut config = review_config();
+        config.allow_substring_match = false;
+        assert!(resolve_in(config.clone(), &ids, "k2m").is_err());
+
+        config.try_allowed_prefixes = true;
+        let resolved = resolve_in(config, &ids, "k2m").unwrap();
+        assert_eq!(resolved.id, "th-k2m");
+        assert_eq!(resolved.match_type, MatchType::PrefixNormalized);
+    }
+
+    #[test]
+    fn test_try_allowed_prefixes_prefers_default() {
+        let ids = known(&["cr-k2m", "th-k2m"]);
+        let mut config = review_config();
+        config.try_allowed_prefixes = true;
+        assert_eq!(resolve_in(config, &ids, "k2m").unwrap().id, "cr-k2m");
+    }
+
+    // ========== Fuzzy suggestions ==========
+
+    fn fuzzy_config() -> ResolverConfig {
+        let mut config = ResolverConfig::new("bd");
+        config.fuzzy = Some(FuzzyConfig::new());
+        config
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("a7x3q9"), &chars("a7x3q9")), 0);
+        assert_eq!(edit_distance(&chars("a7x3q9"), &chars("a7x3g9")), 1);
+        assert_eq!(edit_distance(&chars("a7x3q9"), &chars("a7xq39")), 1);
+        assert_eq!(edit_distance(&chars("a7x3q9"), &chars("a7x3q")), 1);
+        assert_eq!(edit_distance(&chars("a7x3q9"), &chars("7ax3g9")), 2);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("ca"), &chars("abc")), 3);
+    }
+
+    #[test]
+    fn test_find_similar_ids_ranks_by_distance() {
+        let all_ids = ["bd-a7x3gg", "bd-a7x3q9", "bd-k2m4p1", "bogus"];
+        assert_eq!(
+            find_similar_ids(&all_ids, "A7X3Q8", 2),
+            vec!["bd-a7x3q9", "bd-a7x3gg"]
+        );
+        assert_eq!(find_similar_ids(&all_ids, "a7x3q8", 1), vec!["bd-a7x3q9"]);
+    }
+
+    #[test]
+    fn test_find_similar_ids_prefix_and_child_path() {
+        let all_ids = ["bd-a7x3q9", "tk-a7x3q9", "bd-a7x3q9.2"];
+        assert_eq!(
+            find_similar_ids(&all_ids, "tk-a7x3g9", 2),
+            vec!["tk-a7x3q9"]
+        );
+        assert_eq!(
+            find_similar_ids(&all_ids, "a7x3g9.2", 2),
+            vec!["bd-a7x3q9.2"]
+        );
+        assert!(find_similar_ids(&all_ids, "a7x3g9.3", 2).is_empty());
+        assert!(find_similar_ids(&all_ids, "not an id!", 2).is_empty());
+    }
+
+    #[test]
+    fn test_find_similar_ids_short_input() {
+        let all_ids = ["bd-a7x3q9", "bd-k2m4p1"];
+        // Compared against the first three characters of each hash.
+        assert_eq!(find_similar_ids(&all_ids, "a7z", 1), vec!["bd-a7x3q9"]);
+        // The distance is used as given, however short the input.
+        assert_eq!(find_similar_ids(&all_ids, "a8", 1), vec!["bd-a7x3q9"]);
+        assert!(find_similar_ids(&all_ids, "a8", 0).is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_distance_scaled_by_input_length() {
+        let fuzzy = FuzzyConfig::new();
+        assert_eq!(fuzzy.max_distance_for(2), 0);
+        assert_eq!(fuzzy.max_distance_for(3), 1);
+        assert_eq!(fuzzy.max_distance_for(6), 2);
+        assert_eq!(fuzzy.max_distance_for(20), 2);
+        assert_eq!(fuzzy.chars_per_edit(0).max_distance_for(2), 2);
+
+        let ids = known(&["bd-a7x3q9"]);
+        assert_eq!(
+            resolve_in(fuzzy_config(), &ids, "a8"),
+            Err(TerseIdError::NotFound {
+                id: "a8".to_string()
+            })
+        );
+        let mut config = ResolverConfig::new("bd");
+        config.fuzzy = Some(FuzzyConfig::new().chars_per_edit(0));
+        assert!(matches!(
+            resolve_in(config, &ids, "a8"),
+            Err(TerseIdError::NotFoundWithSuggestions { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_suggests_on_typo() {
+        let ids = known(&["bd-a7x3q9", "bd-k2m4p1"]);
+        let err = resolve_in(fuzzy_config(), &ids, "a7x3g9").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::NotFoundWithSuggestions {
+                id: "a7x3g9".to_string(),
+                suggestions: vec!["bd-a7x3q9".to_string()],
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "ID not found: a7x3g9; did you mean bd-a7x3q9?"
+        );
+    }
+
+    #[test]
+    fn test_resolve_without_fuzzy_is_plain_not_found() {
+        let ids = known(&["bd-a7x3q9"]);
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "a7x3g9"),
+            Err(TerseIdError::NotFound {
+                id: "a7x3g9".to_string()
+            })
+        );
+        // Nothing close enough either.
+        assert_eq!(
+            resolve_in(fuzzy_config(), &ids, "k2m4p1"),
+            Err(TerseIdError::NotFound {
+                id: "k2m4p1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_suggestions_capped_and_filtered() {
+        let ids = known(&["bd-a7x3q1", "bd-a7x3q2", "bd-a7x3q3", "tk-a7x3q4"]);
+        let mut config = fuzzy_config();
+        config.fuzzy = Some(FuzzyConfig::new().max_suggestions(2));
+        let err = resolve_in(config, &ids, "a7x3qq").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::NotFoundWithSuggestions {
+                id: "a7x3qq".to_string(),
+                suggestions: vec!["bd-a7x3q1".to_string(), "bd-a7x3q2".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_suggestions_from_bounded_empty_query() {
+        let ids = known(&["bd-a7x3q1", "bd-a7x3q2"]);
+        let recent = known(&["bd-a7x3q2"]);
+        let empty_queries = Cell::new(0);
+        let err = IdResolver::new(fuzzy_config())
+            .resolve(
+                "a7x3qq",
+                |id| ids.iter().any(|k| k == id),
+                |sub| {
+                    if sub.is_empty() {
+                        empty_queries.set(empty_queries.get() + 1);
+                        recent.clone()
+                    } else {
+                        find_matching_ids(&ids, sub)
+                    }
+                },
+            )
+            .unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::NotFoundWithSuggestions {
+                id: "a7x3qq".to_string(),
+                suggestions: recent,
+            }
+        );
+        assert_eq!(empty_queries.get(), 1);
+    }
+
+    #[test]
+    fn test_resolve_never_selects_suggestion() {
+        // A single close candidate is still only a suggestion.
+        let ids = known(&["bd-a7x3q9"]);
+        let mut config = fuzzy_config();
+        config.allow_substring_match = false;
+        assert!(matches!(
+            resolve_in(config, &ids, "bd-a7x3q8"),
+            Err(TerseIdError::NotFoundWithSuggestions { .. })
+        ));
+    }
+
+    #[test]
+    fn test_fuzzy_config_builders() {
+        assert_eq!(FuzzyConfig::default(), FuzzyConfig::new());
+        let fuzzy = FuzzyConfig::new()
+            .max_distance(1)
+            .max_suggestions(5)
+            .chars_per_edit(4);
+        assert_eq!(fuzzy.max_distance, 1);
+        assert_eq!(fuzzy.max_suggestions, 5);
+        assert_eq!(fuzzy.chars_per_edit, 4);
+        assert!(ResolverConfig::new("bd").fuzzy.is_none());
+    }
+
+    // ========== Child paths ==========
+
+    fn family() -> Vec<String> {
+        known(&[
+            "bd-a7x3q9",
+            "bd-a7x3q9.1",
+            "bd-a7x3q9.2",
+            "bd-a7x3q9.2.1",
+            "bd-k2m4p1",
+        ])
+    }
+
+    #[test]
+    fn test_child_path_start() {
+        assert_eq!(child_path_start("a7x.2"), Some(3));
+        assert_eq!(child_path_start("bd-a7x.2.10"), Some(6));
+        assert_eq!(child_path_start("my.app-a7x.1"), Some(10));
+        assert_eq!(child_path_start("a7x"), None);
+        assert_eq!(child_path_start("a7x.z"), None);
+        assert_eq!(child_path_start("a7x.1."), None);
+        assert_eq!(child_path_start("bd-.1"), None);
+    }
+
+    #[test]
+    fn test_resolve_partial_hash_with_child_path() {
+        let ids = family();
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "a7x.2").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9.2");
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "A7X.2.1").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9.2.1");
+
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "x3q.1").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9.1");
+        assert_eq!(resolved.match_type, MatchType::Substring);
+    }
+
+    #[test]
+    fn test_resolve_full_child_id_still_exact() {
+        let ids = family();
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "bd-a7x3q9.2").unwrap();
+        assert_eq!(resolved.match_type, MatchType::Exact);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "a7x3q9.2").unwrap();
+        assert_eq!(resolved.match_type, MatchType::PrefixNormalized);
+    }
+
+    #[test]
+    fn test_resolve_missing_child_names_full_id() {
+        let ids = family();
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "a7x.5"),
+            Err(TerseIdError::NotFound {
+                id: "bd-a7x3q9.5".to_string()
+            })
+        );
+        // No root matches at all: the input is reported as typed.
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "zzz.1"),
+            Err(TerseIdError::NotFound {
+                id: "zzz.1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_child_path_ambiguous_root() {
+        let ids = known(&["bd-a7x1", "bd-a7x1.1", "bd-a7x2.1"]);
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "a7x.1"),
+            Err(TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: vec!["bd-a7x1".to_string(), "bd-a7x2".to_string()],
+                total: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_child_of_missing_root() {
+        let ids = known(&["bd-a7x3q9.2"]);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "a7x.2").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9.2");
+    }
+
+    #[test]
+    fn test_roots_of() {
+        assert_eq!(
+            roots_of(known(&["bd-a7x.2", "BD-A7X", "bd-k2m.1.1", "nope"])),
+            vec!["bd-a7x", "bd-k2m"]
+        );
+    }
+
+    // ========== Partial IDs with a prefix ==========
+
+    #[test]
+    fn test_resolve_prefixed_partial_hash() {
+        let ids = known(&["bd-a7x3q9", "bd-k2m4p1"]);
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "bd-a7").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "BD-X3Q").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9");
+        assert_eq!(resolved.match_type, MatchType::Substring);
+    }
+
+    #[test]
+    fn test_resolve_prefixed_partial_limited_to_prefix() {
+        let ids = known(&["bd-a7x3q9", "th-a7k2m4"]);
+        let mut config = ResolverConfig::new("bd");
+        config.allowed_prefixes = vec!["th".to_string()];
+        let resolved = resolve_in(config.clone(), &ids, "th-a7").unwrap();
+        assert_eq!(resolved.id, "th-a7k2m4");
+        // Without a prefix both namespaces match.
+        assert!(matches!(
+            resolve_in(config, &ids, "a7"),
+            Err(TerseIdError::AmbiguousId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_prefixed_partial_ambiguous_and_missing() {
+        let ids = known(&["bd-a7x3q9", "bd-a7y2k1"]);
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "bd-a7"),
+            Err(TerseIdError::AmbiguousId {
+                partial: "bd-a7".to_string(),
+                matches: vec!["bd-a7x3q9".to_string(), "bd-a7y2k1".to_string()],
+                total: 2,
+            })
+        );
+        assert!(matches!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "bd-"),
+            Err(TerseIdError::NotFound { .. })
+        ));
+        assert!(matches!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "bd-zz"),
+            Err(TerseIdError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_prefixed_partial_with_child_path() {
+        let ids = family();
+        let resolved = resolve_in(ResolverConfig::new("bd"), &ids, "bd-a7.2").unwrap();
+        assert_eq!(resolved.id, "bd-a7x3q9.2");
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+    }
+
+    // ========== Resolving against an IdIndex ==========
+
+    #[test]
+    fn test_resolve_indexed_matches_closures() {
+        let ids = known(&[
+            "bd-a7x3q9",
+            "bd-a7x3q9.2",
+            "bd-k2m4p1",
+            "bd-qa7x1",
+            "th-r9p2k4",
+        ]);
+        let index = IdIndex::from_ids(&ids);
+        let mut config = ResolverConfig::new("bd");
+        config.allowed_prefixes = vec!["th".to_string()];
+        config.fuzzy = Some(FuzzyConfig::new());
+        let resolver = IdResolver::new(config.clone());
+        for input in [
+            "bd-a7x3q9",
+            "a7x3q9.2",
+            "k2m",
+            "bd-k2",
+            "th-r9",
+            "r9p",
+            "qa7",
+            "a7x.2",
+            "a7x.9",
+            "k2m4p2",
+            "zzz",
+            "bd-",
+            "m4p",
+        ] {
+            assert_eq!(
+                resolver.resolve_indexed(input, &index),
+                resolve_in(config.clone(), &ids, input),
+                "{input}"
+            );
+        }
+    }
+
+    // ========== Abbreviation ==========
+
+    #[test]
+    fn test_shortest_unique() {
+        let index = IdIndex::from_ids(["bd-a7x3q9", "bd-a7y2k1", "bd-k2m4p1", "bd-k2"]);
+        let resolver = IdResolver::new(ResolverConfig::new("bd"));
+        assert_eq!(
+            resolver.shortest_unique("BD-A7X3Q9", &index).unwrap(),
+            "a7x"
+        );
+        assert_eq!(
+            resolver.shortest_unique("bd-a7y2k1", &index).unwrap(),
+            "a7y"
+        );
+        // "k2" is an ID of its own, so bd-k2m4p1 needs one more character.
+        assert_eq!(resolver.shortest_unique("bd-k2", &index).unwrap(), "k2");
+        assert_eq!(
+            resolver.shortest_unique("bd-k2m4p1", &index).unwrap(),
+            "k2m"
+        );
+    }
+
+    #[test]
+    fn test_shortest_unique_child_path() {
+        let index = IdIndex::from_ids(["bd-a7x3q9", "bd-a7x3q9.2", "bd-k2m4p1.2"]);
+        let resolver = IdResolver::new(ResolverConfig::new("bd"));
+        // The parent is shortened just like an ID without children.
+        assert_eq!(
+            resolver.shortest_unique("bd-a7x3q9", &index).unwrap(),
+            "a7x"
+        );
+        assert_eq!(
+            resolver.shortest_unique("bd-a7x3q9.2", &index).unwrap(),
+            "a7x.2"
+        );
+        assert_eq!(
+            resolver.shortest_unique("bd-k2m4p1.2", &index).unwrap(),
+            "k2m.2"
+        );
+    }
+
+    #[test]
+    fn test_shortest_unique_other_prefix() {
+        let index = IdIndex::from_ids(["bd-a7x3q9", "th-a7y2k1"]);
+        let mut config = ResolverConfig::new("bd");
+        config.allowed_prefixes = vec!["th".to_string()];
+        let resolver = IdResolver::new(config);
+        // Bare "a7y" resolves too, since it only matches in th.
+        assert_eq!(
+            resolver.shortest_unique("th-a7y2k1", &index).unwrap(),
+            "a7y"
+        );
+
+        let index = IdIndex::from_ids(["bd-a7x3q9", "th-a7x3q8"]);
+        assert_eq!(
+            resolver.shortest_unique("th-a7x3q8", &index).unwrap(),
+            "th-a7x"
+        );
+    }
+
+    #[test]
+    fn test_shortest_unique_errors() {
+        let index = IdIndex::from_ids(["bd-a7x3q9", "tk-b8y"]);
+        let resolver = IdResolver::new(ResolverConfig::new("bd"));
+        assert!(matches!(
+            resolver.shortest_unique("nope", &index),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+        assert!(matches!(
+            resolver.shortest_unique("tk-b8y", &index),
+            Err(TerseIdError::PrefixMismatch { .. })
+        ));
+        assert_eq!(
+            resolver.shortest_unique("bd-k2m", &index),
+            Err(TerseIdError::NotFound {
+                id: "bd-k2m".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_abbreviate_all() {
+        let ids = [
+            "bd-a7x3q9",
+            "bd-a7y2k1",
+            "BD-K2M4P1",
+            "bd-k2m4p1.3",
+            "junk",
+            "tk-b8y",
+        ];
+        let resolver = IdResolver::new(ResolverConfig::new("bd"));
+        // bd-k2m4p1 is shortened despite having a child.
+        assert_eq!(
+            resolver.abbreviate_all(&ids, 4),
+            vec!["a7x3", "a7y2", "k2m4", "k2m4.3", "junk", "tk-b8y"]
+        );
+        assert_eq!(
+            resolver.abbreviate_all(&ids, 0),
+            vec!["a7x", "a7y", "k2m", "k2m.3", "junk", "tk-b8y"]
+        );
+        assert_eq!(
+            resolver.abbreviate_all(&ids, 1),
+            resolver.abbreviate_all(&ids, 3)
+        );
+        // min_len beyond the hash length falls back to the whole hash.
+        assert_eq!(resolver.abbreviate_all(&["bd-a7x"], 10), vec!["a7x"]);
+    }
+
+    #[test]
+    fn test_abbreviations_resolve_back() {
+        let ids = known(&[
+            "bd-a7x3q9",
+            "bd-a7x3q9.1",
+            "bd-a7x4q8",
+            "bd-qa7x1",
+            "bd-7x3q9a",
+            "th-a7y3q7",
+            "th-r9p2k4.2.1",
+        ]);
+        let mut config = ResolverConfig::new("bd");
+        config.allowed_prefixes = vec!["th".to_string()];
+        let resolver = IdResolver::new(config.clone());
+        let shorts = resolver.abbreviate_all(&ids, 1);
+        for (id, short) in ids.iter().zip(&shorts) {
+            assert_eq!(resolve_in(config.clone(), &ids, short).unwrap().id, *id);
+        }
+        // The parent is as short as its siblings allow, not its full hash.
+        assert_eq!(
+            shorts,
+            vec!["a7x3", "a7x3.1", "a7x4", "qa7", "7x3", "a7y", "r9p.2.1"]
+        );
+    }
+
+    // ========== Ambiguity ranking ==========
+
+    #[test]
+    fn test_ambiguous_hash_prefix_ranked_shortest_first() {
+        let ids = known(&["bd-a7x3q9", "bd-a7x1.1", "bd-a7x1"]);
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "a7x"),
+            Err(TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: known(&["bd-a7x1", "bd-a7x3q9"]),
+                total: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_substring_ranked_by_position() {
+        let ids = known(&["bd-qqa7x", "bd-xa7x12", "bd-za7x1.1", "bd-za7x1"]);
+        assert_eq!(
+            resolve_in(ResolverConfig::new("bd"), &ids, "a7x"),
+            Err(TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: known(&["bd-za7x1", "bd-xa7x12", "bd-qqa7x"]),
+                total: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_matches_capped() {
+        let mut ids: Vec<String> = (0..15).map(|n| format!("bd-a7x{n:02}")).collect();
+        ids.push("bd-a7x9".to_string());
+        let mut config = ResolverConfig::new("bd");
+        config.max_ambiguous_matches = 3;
+        let err = resolve_in(config, &ids, "a7x").unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: known(&["bd-a7x9", "bd-a7x00", "bd-a7x01"]),
+                total: 16,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "ambiguous ID 'a7x': 16 matches: bd-a7x9, bd-a7x00, bd-a7x01 and 13 more"
+        );
+
+        let err = resolve_in(ResolverConfig::new("bd"), &ids, "a7x").unwrap_err();
+        assert!(matches!(
+            err,
+            TerseIdError::AmbiguousId { matches, total: 16, .. } if matches.len() == 10
+        ));
+    }
+
+    // ========== Choosing among ambiguous matches ==========
+
+    fn choose_in(
+        ids: &[String],
+        input: &str,
+        choose: impl FnOnce(&str, &[String], usize) -> Option<usize>,
+    ) -> Result<ResolvedId> {
+        IdResolver::new(ResolverConfig::new("bd")).resolve_or_choose(
+            input,
+            |id| ids.iter().any(|k| k == id),
+            |sub| find_matching_ids(ids, sub),
+            choose,
+        )
+    }
+
+    #[test]
+    fn test_resolve_or_choose_picks_candidate() {
+        let ids = known(&["bd-a7x3q9", "bd-a7x4k2"]);
+        let resolved = choose_in(&ids, " A7X ", |partial, candidates, total| {
+            assert_eq!(partial, "a7x");
+            assert_eq!(candidates, known(&["bd-a7x3q9", "bd-a7x4k2"]).as_slice());
+            assert_eq!(total, 2);
+            Some(1)
+        })
+        .unwrap();
+        assert_eq!(resolved.id, "bd-a7x4k2");
+        assert_eq!(resolved.match_type, MatchType::Chosen);
+        assert_eq!(resolved.original_input, " A7X ");
+        assert_eq!(
+            resolved.normalizations,
+            vec![Normalization::Trimmed, Normalization::Lowercased]
+        );
+    }
+
+    #[test]
+    fn test_resolve_or_choose_declined_keeps_error() {
+        let ids = known(&["bd-a7x3q9", "bd-a7x4k2"]);
+        let expected = resolve_in(ResolverConfig::new("bd"), &ids, "a7x");
+        assert!(matches!(expected, Err(TerseIdError::AmbiguousId { .. })));
+        assert_eq!(choose_in(&ids, "a7x", |_, _, _| None), expected);
+        assert_eq!(choose_in(&ids, "a7x", |_, _, _| Some(2)), expected);
+    }
+
+    #[test]
+    fn test_resolve_or_choose_not_called_when_unambiguous() {
+        let ids = known(&["bd-a7x3q9", "bd-k2m4p1"]);
+        let resolved = choose_in(&ids, "a7x", |_, _, _| panic!("chooser called")).unwrap();
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+        assert!(matches!(
+            choose_in(&ids, "zzz", |_, _, _| panic!("chooser called")),
+            Err(TerseIdError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_or_choose_reattaches_child_path() {
+        let ids = known(&["bd-a7x1", "bd-a7x1.1", "bd-a7x2", "bd-a7x2.1"]);
+        let resolved = choose_in(&ids, "a7x.1", |partial, candidates, _| {
+            assert_eq!(partial, "a7x");
+            candidates.iter().position(|id| id == "bd-a7x2")
+        })
+        .unwrap();
+        assert_eq!(resolved.id, "bd-a7x2.1");
+        assert_eq!(resolved.match_type, MatchType::Chosen);
+
+        let ids = known(&["bd-a7x1", "bd-a7x1.1", "bd-a7x2"]);
+        assert_eq!(
+            choose_in(&ids, "a7x.1", |_, candidates, _| {
+                candidates.iter().position(|id| id == "bd-a7x2")
+            }),
+            Err(TerseIdError::NotFound {
+                id: "bd-a7x2.1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_or_choose_after_lookalikes() {
+        let ids = known(&["bd-a7x1q9", "bd-a7x1q9.1", "bd-a7x1k2", "bd-a7x1k2.1"]);
+        let mut config = ResolverConfig::new("bd");
+        config.normalizer = InputNormalizer::new().lookalikes(true);
+        let resolved = IdResolver::new(config)
+            .resolve_or_choose(
+                "A7XL.1",
+                |id| ids.iter().any(|k| k == id),
+                |sub| find_matching_ids(&ids, sub),
+                |partial, candidates, _| {
+                    assert_eq!(partial, "a7x1");
+                    candidates.iter().position(|id| id == "bd-a7x1q9")
+                },
+            )
+            .unwrap();
+        assert_eq!(resolved.id, "bd-a7x1q9.1");
+        assert_eq!(resolved.match_type, MatchType::Chosen);
+        assert_eq!(
+            resolved.normalizations,
+            vec![
+                Normalization::Lowercased,
+                Normalization::Lookalikes {
+                    from: "a7xl.1".to_string(),
+                    to: "a7x1.1".to_string(),
+                },
+            ]
+        );
+    }
+
+    // ========== Batch resolution ==========
+
+    /// Wraps an index and counts the batch calls made against it.
+    struct CountingStore {
+        index: IdIndex,
+        exists_calls: Cell<usize>,
+        matching_calls: Cell<usize>,
+    }
+
+    impl CountingStore {
+        fn new(ids: &[String]) -> Self {
+            Self {
+                index: IdIndex::from_ids(ids),
+                exists_calls: Cell::new(0),
+                matching_calls: Cell::new(0),
+            }
+        }
+    }
+
+    impl IdStore for CountingStore {
+        fn exists_many(&self, ids: &[&str]) -> Vec<bool> {
+            self.exists_calls.set(self.exists_calls.get() + 1);
+            self.index.exists_many(ids)
+        }
+
+        fn find_matching_many(&self, hash_substrings: &[&str]) -> Vec<Vec<String>> {
+            self.matching_calls.set(self.matching_calls.get() + 1);
+            self.index.find_matching_many(hash_substrings)
+        }
+    }
+
+    #[test]
+    fn test_resolve_many_matches_resolve() {
+        let ids = family();
+        let mut config = ResolverConfig::new("bd");
+        config.fuzzy = Some(FuzzyConfig::new());
+        let inputs = [
+            "bd-a7x3q9",
+            "k2m",
+            "a7x.2",
+            "a7x.9",
+            "zzz",
+            "k2m4p2",
+            "#BD-K2M4P1",
+            "x3q9.2.1",
+            "a7",
+        ];
+        let store = CountingStore::new(&ids);
+        let batch = IdResolver::new(config.clone()).resolve_many(&inputs, &store);
+        assert_eq!(batch.results.len(), inputs.len());
+        for (input, result) in inputs.iter().zip(&batch.results) {
+            assert_eq!(*result, resolve_in(config.clone(), &ids, input), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_many_batches_per_round() {
+        let ids = family();
+        let inputs: Vec<String> = ids
+            .iter()
+            .map(|id| id.trim_start_matches("bd-").to_string())
+            .collect();
+        let store = CountingStore::new(&ids);
+        let batch = IdResolver::new(ResolverConfig::new("bd")).resolve_many(&inputs, &store);
+        assert!(batch.results.iter().all(Result::is_ok));
+        // Lookups not made yet count as misses, so the later stages' lookups
+        // go out in the same round.
+        assert_eq!(store.exists_calls.get(), 1);
+        assert_eq!(store.matching_calls.get(), 1);
+
+        let store = CountingStore::new(&ids);
+        let batch =
+            IdResolver::new(ResolverConfig::new("bd")).resolve_many(&["a7x.2", "k2"], &store);
+        assert!(batch.results.iter().all(Result::is_ok));
+        // The child can only be checked once its root is known.
+        assert_eq!(store.exists_calls.get(), 2);
+        assert_eq!(store.matching_calls.get(), 1);
+    }
+
+    #[test]
+    fn test_resolve_many_dedups_and_flags_duplicates() {
+        let ids = family();
+        let store = CountingStore::new(&ids);
+        let batch = IdResolver::new(ResolverConfig::new("bd"))
+            .resolve_many(&["k2m", "a7x.1", "k2m", "BD-K2M4P1", "nope"], &store);
+        assert_eq!(batch.results[0], batch.results[2]);
+        assert_eq!(
+            batch.results[3].as_ref().unwrap().original_input,
+            "BD-K2M4P1"
+        );
+        assert!(batch.results[4].is_err());
+        assert_eq!(
+            batch.duplicates,
+            vec![("bd-k2m4p1".to_string(), vec![0, 2, 3])]
+        );
+    }
+
+    #[test]
+    fn test_resolve_many_empty() {
+        let store = CountingStore::new(&[]);
+        let inputs: [&str; 0] = [];
+        let batch = IdResolver::new(ResolverConfig::new("bd")).resolve_many(&inputs, &store);
+        assert!(batch.results.is_empty());
+        assert!(batch.duplicates.is_empty());
+        assert_eq!(store.exists_calls.get(), 0);
+    }
 }
@@ -0,0 +1,581 @@
+//! The stages of [`IdResolver`](crate::resolve::IdResolver)'s pipeline.
+//!
+//! Resolution runs a list of [`ResolutionStage`]s in order until one of them
+//! matches. [`default_stages`] returns the built-in ones; custom stages such as
+//! alias lookup can be added anywhere in the list with
+//! [`IdResolver::stages`](crate::resolve::IdResolver::stages).
+
+use crate::error::{Result, TerseIdError};
+use crate::parse::{ParsedId, parse_id};
+use crate::resolve::{MatchType, ResolverConfig};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+
+/// The input and lookups available to a [`ResolutionStage`].
+pub struct StageContext<'a> {
+    input: &'a str,
+    config: &'a ResolverConfig,
+    exists_fn: &'a dyn Fn(&str) -> bool,
+    substring_match_fn: &'a dyn Fn(&str) -> Vec<String>,
+    candidates: OnceCell<Vec<String>>,
+}
+
+impl<'a> StageContext<'a> {
+    /// Creates a context, for example to test a stage on its own.
+    #[must_use]
+    pub const fn new(
+        input: &'a str,
+        config: &'a ResolverConfig,
+        exists_fn: &'a dyn Fn(&str) -> bool,
+        substring_match_fn: &'a dyn Fn(&str) -> Vec<String>,
+    ) -> Self {
+        Self {
+            input,
+            config,
+            exists_fn,
+            substring_match_fn,
+            candidates: OnceCell::new(),
+        }
+    }
+
+    /// Returns the normalized input. For input with a child path this is the
+    /// whole input on the first pass and the root part on the second.
+    #[must_use]
+    pub const fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Returns the resolver's configuration.
+    #[must_use]
+    pub const fn config(&self) -> &'a ResolverConfig {
+        self.config
+    }
+
+    /// Returns true if `id` exists.
+    #[must_use]
+    pub fn exists(&self, id: &str) -> bool {
+        (self.exists_fn)(id)
+    }
+
+    /// Returns the IDs whose hash contains `hash_substring`.
+    #[must_use]
+    pub fn find_matching(&self, hash_substring: &str) -> Vec<String> {
+        (self.substring_match_fn)(hash_substring)
+    }
+}
+
+/// One step of ID resolution.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::{IdResolver, MatchType, ResolutionStage, ResolverConfig, StageContext};
+/// use terseid::stage::default_stages;
+///
+/// /// Resolves `@last` to the most recently referenced ID.
+/// struct LastReferenced(String);
+///
+/// impl ResolutionStage for LastReferenced {
+///     fn name(&self) -> &str {
+///         "last-referenced"
+///     }
+///
+///     fn resolve(&self, context: &StageContext<'_>) -> terseid::Result<Option<String>> {
+///         Ok((context.input() == "@last").then(|| self.0.clone()))
+///     }
+/// }
+///
+/// let mut stages = default_stages();
+/// stages.insert(0, Box::new(LastReferenced("bd-a7x3q9".to_string())));
+/// let resolver = IdResolver::new(ResolverConfig::new("bd")).stages(stages);
+///
+/// let resolved = resolver.resolve("@last", |_| false, |_| vec![]).unwrap();
+/// assert_eq!(resolved.id, "bd-a7x3q9");
+/// assert_eq!(resolved.match_type, MatchType::Custom("last-referenced".to_string()));
+/// ```
+pub trait ResolutionStage: Send + Sync {
+    /// A short name for the stage, recorded in [`MatchType::Custom`].
+    fn name(&self) -> &str;
+
+    /// Tries to resolve `context.input()`.
+    ///
+    /// Returns `Ok(Some(id))` on a match, `Ok(None)` to pass the input on to
+    /// the next stage.
+    ///
+    /// # Errors
+    ///
+    /// An error ends resolution and is returned to the caller, e.g.
+    /// `AmbiguousId` when several IDs match equally well.
+    fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>>;
+
+    /// How a match by this stage is reported. Defaults to
+    /// `MatchType::Custom(name)`.
+    fn match_type(&self) -> MatchType {
+        MatchType::Custom(self.name().to_string())
+    }
+}
+
+/// Matches input that is an existing ID.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactStage;
+
+impl ResolutionStage for ExactStage {
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>> {
+        Ok(context
+            .exists(context.input())
+            .then(|| context.input().to_string()))
+    }
+
+    fn match_type(&self) -> MatchType {
+        MatchType::Exact
+    }
+}
+
+/// Matches input without a dash once `default_prefix-` is prepended, and with
+/// `try_allowed_prefixes`, each allowed prefix in turn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefixNormalizedStage;
+
+impl ResolutionStage for PrefixNormalizedStage {
+    fn name(&self) -> &'static str {
+        "prefix-normalized"
+    }
+
+    fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>> {
+        let input = context.input();
+        if input.contains('-') {
+            return Ok(None);
+        }
+        let config = context.config();
+        let extra_prefixes = if config.try_allowed_prefixes {
+            config.allowed_prefixes.as_slice()
+        } else {
+            &[]
+        };
+        Ok(core::iter::once(&config.default_prefix)
+            .chain(extra_prefixes)
+            .map(|prefix| format!("{prefix}-{input}"))
+            .find(|prefixed| context.exists(prefixed)))
+    }
+
+    fn match_type(&self) -> MatchType {
+        MatchType::PrefixNormalized
+    }
+}
+
+/// Matches IDs whose hash starts with the input (git-style short IDs).
+///
+/// Does nothing unless `allow_substring_match` is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashPrefixStage;
+
+impl ResolutionStage for HashPrefixStage {
+    fn name(&self) -> &'static str {
+        "hash-prefix"
+    }
+
+    fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>> {
+        let (partial_hash, candidates) = candidates(context);
+        let matches = candidates
+            .iter()
+            .filter(|id| hash_starts_with(id, partial_hash))
+            .cloned()
+            .collect();
+        unique(context, partial_hash, matches)
+    }
+
+    fn match_type(&self) -> MatchType {
+        MatchType::HashPrefix
+    }
+}
+
+/// Matches IDs whose hash contains the input other than at the start.
+///
+/// Does nothing unless `allow_substring_match` is set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubstringStage;
+
+impl ResolutionStage for SubstringStage {
+    fn name(&self) -> &'static str {
+        "substring"
+    }
+
+    fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>> {
+        let (partial_hash, candidates) = candidates(context);
+        let matches = candidates
+            .iter()
+            .filter(|id| !hash_starts_with(id, partial_hash))
+            .cloned()
+            .collect();
+        unique(context, partial_hash, matches)
+    }
+
+    fn match_type(&self) -> MatchType {
+        MatchType::Substring
+    }
+}
+
+/// The built-in stages, used by resolvers without a custom pipeline.
+pub(crate) static BUILT_IN_STAGES: [&dyn ResolutionStage; 4] = [
+    &ExactStage,
+    &PrefixNormalizedStage,
+    &HashPrefixStage,
+    &SubstringStage,
+];
+
+/// Returns the built-in stages in their default order: exact, prefix
+/// normalized, hash prefix, substring.
+#[must_use]
+pub fn default_stages() -> Vec<Box<dyn ResolutionStage>> {
+    vec![
+        Box::new(ExactStage),
+        Box::new(PrefixNormalizedStage),
+        Box::new(HashPrefixStage),
+        Box::new(SubstringStage),
+    ]
+}
+
+/// The hash part searched for and the allowed IDs whose hash contains it.
+///
+/// With a dash, only the part after it is searched, within that prefix. The
+/// list is computed on first use and shared by the stages that run on the same
+/// context, so `substring_match_fn` is called at most once per input.
+fn candidates<'c>(context: &'c StageContext<'_>) -> (&'c str, &'c [String]) {
+    let input = context.input();
+    let (prefix, partial_hash) = match input.rsplit_once('-') {
+        Some((prefix, hash)) => (Some(prefix), hash),
+        None => (None, input),
+    };
+    let candidates = context
+        .candidates
+        .get_or_init(|| find_candidates(context, prefix, partial_hash));
+    (partial_hash, candidates)
+}
+
+/// Queries `substring_match_fn` for [`candidates`].
+fn find_candidates(
+    context: &StageContext<'_>,
+    prefix: Option<&str>,
+    partial_hash: &str,
+) -> Vec<String> {
+    let config = context.config();
+    if !config.allow_substring_match
+        || partial_hash.is_empty()
+        || prefix.is_some_and(|prefix| !config.is_allowed_prefix(prefix))
+    {
+        return Vec::new();
+    }
+    // An ID's descendants contain its hash too; only IDs as deep as the
+    // input compete, so `a7x` picks `bd-a7x3q9` over `bd-a7x3q9.1`.
+    let depth = partial_hash.matches('.').count();
+    let mut matches = context.find_matching(partial_hash);
+    matches.retain(|id| {
+        parse_id(id).is_ok_and(|parsed| {
+            parsed.depth() == depth
+                && prefix.map_or_else(
+                    || config.is_allowed_prefix(&parsed.prefix),
+                    |prefix| parsed.prefix == prefix,
+                )
+        })
+    });
+    matches
+}
+
+/// One match succeeds, several are ambiguous.
+///
+/// Ambiguous matches are ranked by [`match_rank`] and capped at
+/// `max_ambiguous_matches`.
+fn unique(
+    context: &StageContext<'_>,
+    partial_hash: &str,
+    mut matches: Vec<String>,
+) -> Result<Option<String>> {
+    match matches.len() {
+        0 => Ok(None),
+        1 => Ok(matches.pop()),
+        total => {
+            matches.sort_by_cached_key(|id| match_rank(id, partial_hash));
+            matches.truncate(context.config().max_ambiguous_matches);
+            Err(TerseIdError::AmbiguousId {
+                partial: context.input().to_string(),
+                matches,
+                total,
+            })
+        }
+    }
+}
+
+/// Sort key for an ambiguous match: where `partial_hash` starts in the hash,
+/// then hash length, then the parsed ID, so `bd-a7x.2` comes before
+/// `bd-a7x.10`. Lower is better; IDs that don't parse or don't contain
+/// `partial_hash` come last.
+fn match_rank(id: &str, partial_hash: &str) -> (usize, usize, Option<ParsedId>) {
+    let Ok(parsed) = parse_id(id) else {
+        return (usize::MAX, usize::MAX, None);
+    };
+    let position = parsed.hash.find(partial_hash).unwrap_or(usize::MAX);
+    (position, parsed.hash.len(), Some(parsed))
+}
+
+/// Returns true if `id` parses and its hash starts with `hash_prefix`.
+fn hash_starts_with(id: &str, hash_prefix: &str) -> bool {
+    parse_id(id).is_ok_and(|parsed| parsed.hash.starts_with(hash_prefix))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::resolve::{IdResolver, find_matching_ids};
+
+    fn known(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(ToString::to_string).collect()
+    }
+
+    fn run(
+        stage: &dyn ResolutionStage,
+        config: &ResolverConfig,
+        ids: &[String],
+        input: &str,
+    ) -> Result<Option<String>> {
+        let exists_fn = |id: &str| ids.iter().any(|k| k == id);
+        let substring_fn = |sub: &str| find_matching_ids(ids, sub);
+        stage.resolve(&StageContext::new(input, config, &exists_fn, &substring_fn))
+    }
+
+    /// Maps fixed aliases to IDs.
+    struct Aliases(Vec<(&'static str, &'static str)>);
+
+    impl ResolutionStage for Aliases {
+        fn name(&self) -> &'static str {
+            "alias"
+        }
+
+        fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>> {
+            Ok(self
+                .0
+                .iter()
+                .find(|(alias, _)| *alias == context.input())
+                .map(|(_, id)| (*id).to_string()))
+        }
+    }
+
+    /// Always fails.
+    struct Refuse;
+
+    impl ResolutionStage for Refuse {
+        fn name(&self) -> &'static str {
+            "refuse"
+        }
+
+        fn resolve(&self, context: &StageContext<'_>) -> Result<Option<String>> {
+            Err(TerseIdError::NotFound {
+                id: format!("refused {}", context.input()),
+            })
+        }
+    }
+
+    fn resolve_with(
+        stages: Vec<Box<dyn ResolutionStage>>,
+        ids: &[String],
+        input: &str,
+    ) -> Result<crate::resolve::ResolvedId> {
+        IdResolver::new(ResolverConfig::new("bd"))
+            .stages(stages)
+            .resolve(
+                input,
+                |id| ids.iter().any(|k| k == id),
+                |sub| find_matching_ids(ids, sub),
+            )
+    }
+
+    // ========== Built-in stages ==========
+
+    #[test]
+    fn test_builtin_stages() {
+        let config = ResolverConfig::new("bd");
+        let ids = known(&["bd-a7x3q9", "bd-qa7x1"]);
+        assert_eq!(
+            run(&ExactStage, &config, &ids, "bd-qa7x1").unwrap(),
+            Some("bd-qa7x1".to_string())
+        );
+        assert_eq!(run(&ExactStage, &config, &ids, "qa7x1").unwrap(), None);
+        assert_eq!(
+            run(&PrefixNormalizedStage, &config, &ids, "qa7x1").unwrap(),
+            Some("bd-qa7x1".to_string())
+        );
+        assert_eq!(
+            run(&PrefixNormalizedStage, &config, &ids, "bd-qa7x1").unwrap(),
+            None
+        );
+        assert_eq!(
+            run(&HashPrefixStage, &config, &ids, "a7x").unwrap(),
+            Some("bd-a7x3q9".to_string())
+        );
+        assert_eq!(
+            run(&SubstringStage, &config, &ids, "a7x").unwrap(),
+            Some("bd-qa7x1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_builtin_names_and_match_types() {
+        let stages = default_stages();
+        let names: Vec<&str> = stages.iter().map(|stage| stage.name()).collect();
+        assert_eq!(
+            names,
+            vec!["exact", "prefix-normalized", "hash-prefix", "substring"]
+        );
+        assert_eq!(stages[2].match_type(), MatchType::HashPrefix);
+        assert_eq!(
+            Aliases(vec![]).match_type(),
+            MatchType::Custom("alias".to_string())
+        );
+    }
+
+    #[test]
+    fn test_substring_stages_respect_config() {
+        let mut config = ResolverConfig::new("bd");
+        config.allow_substring_match = false;
+        let ids = known(&["bd-a7x3q9"]);
+        assert_eq!(run(&HashPrefixStage, &config, &ids, "a7x").unwrap(), None);
+
+        let ids = known(&["bd-a7x3q9", "bd-a7x4k2"]);
+        assert!(matches!(
+            run(&HashPrefixStage, &ResolverConfig::new("bd"), &ids, "a7x"),
+            Err(TerseIdError::AmbiguousId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ambiguous_ties_broken_numerically() {
+        let config = ResolverConfig::new("bd");
+        let exists_fn = |_: &str| false;
+        let substring_fn = |_: &str| Vec::new();
+        let context = StageContext::new("a7x", &config, &exists_fn, &substring_fn);
+        let matches = known(&["bd-a7x.10", "bd-a7x.2", "bd-a7x.1.3", "bd-a7x.1"]);
+        assert_eq!(
+            unique(&context, "a7x", matches),
+            Err(TerseIdError::AmbiguousId {
+                partial: "a7x".to_string(),
+                matches: known(&["bd-a7x.1", "bd-a7x.1.3", "bd-a7x.2", "bd-a7x.10"]),
+                total: 4,
+            })
+        );
+    }
+
+    // ========== Pipelines ==========
+
+    #[test]
+    fn test_custom_stage_first() {
+        let ids = known(&["bd-a7x3q9", "bd-k2m4p1"]);
+        let mut stages = default_stages();
+        stages.insert(0, Box::new(Aliases(vec![("login-bug", "bd-k2m4p1")])));
+        let resolved = resolve_with(stages, &ids, "Login-Bug").unwrap();
+        assert_eq!(resolved.id, "bd-k2m4p1");
+        assert_eq!(resolved.match_type, MatchType::Custom("alias".to_string()));
+    }
+
+    #[test]
+    fn test_custom_stage_last_only_runs_when_builtins_miss() {
+        let ids = known(&["bd-a7x3q9", "bd-k2m4p1"]);
+        let mut stages = default_stages();
+        stages.push(Box::new(Aliases(vec![
+            ("a7x", "bd-k2m4p1"),
+            ("nope", "bd-k2m4p1"),
+        ])));
+        let resolved = resolve_with(stages, &ids, "a7x").unwrap();
+        assert_eq!(resolved.match_type, MatchType::HashPrefix);
+
+        let mut stages = default_stages();
+        stages.push(Box::new(Aliases(vec![("nope", "bd-k2m4p1")])));
+        let resolved = resolve_with(stages, &ids, "nope").unwrap();
+        assert_eq!(resolved.id, "bd-k2m4p1");
+    }
+
+    #[test]
+    fn test_reordered_and_reduced_pipelines() {
+        // "a7x" is a hash prefix of one ID and an exact ID of its own.
+        let ids = known(&["bd-a7x", "bd-a7x3q9"]);
+        let stages: Vec<Box<dyn ResolutionStage>> =
+            vec![Box::new(HashPrefixStage), Box::new(PrefixNormalizedStage)];
+        assert!(matches!(
+            resolve_with(stages, &ids, "a7x"),
+            Err(TerseIdError::AmbiguousId { .. })
+        ));
+
+        let stages: Vec<Box<dyn ResolutionStage>> = vec![Box::new(ExactStage)];
+        assert_eq!(
+            resolve_with(stages, &ids, "a7x"),
+            Err(TerseIdError::NotFound {
+                id: "a7x".to_string()
+            })
+        );
+        assert!(resolve_with(Vec::new(), &ids, "bd-a7x").is_err());
+    }
+
+    #[test]
+    fn test_stage_error_stops_pipeline() {
+        let ids = known(&["bd-a7x3q9"]);
+        let mut stages = default_stages();
+        stages.insert(1, Box::new(Refuse));
+        assert_eq!(
+            resolve_with(stages, &ids, "bd-a7x3q9").unwrap().id,
+            "bd-a7x3q9"
+        );
+
+        let mut stages = default_stages();
+        stages.insert(1, Box::new(Refuse));
+        assert_eq!(
+            resolve_with(stages, &ids, "a7x"),
+            Err(TerseIdError::NotFound {
+                id: "refused a7x".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_custom_stage_resolves_child_root() {
+        let ids = known(&["bd-k2m4p1", "bd-k2m4p1.3"]);
+        let mut stages = default_stages();
+        stages.insert(0, Box::new(Aliases(vec![("login", "bd-k2m4p1")])));
+        let resolved = resolve_with(stages, &ids, "login.3").unwrap();
+        assert_eq!(resolved.id, "bd-k2m4p1.3");
+        assert_eq!(resolved.match_type, MatchType::Custom("alias".to_string()));
+    }
+
+    #[test]
+    fn test_custom_match_with_disallowed_prefix_skipped() {
+        let ids = known(&["bd-k2m4p1"]);
+        let mut stages = default_stages();
+        stages.insert(0, Box::new(Aliases(vec![("k2m", "tk-b8y")])));
+        let resolved = resolve_with(stages, &ids, "k2m").unwrap();
+        assert_eq!(resolved.id, "bd-k2m4p1");
+    }
+
+    #[test]
+    fn test_stage_names() {
+        let resolver = IdResolver::new(ResolverConfig::new("bd"));
+        assert_eq!(resolver.stage_names().count(), 4);
+        let resolver = resolver.stages(vec![Box::new(Aliases(vec![]))]);
+        assert_eq!(resolver.stage_names().collect::<Vec<_>>(), vec!["alias"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_custom_match_type_serde() {
+        let match_type = MatchType::Custom("alias".to_string());
+        let json = serde_json::to_string(&match_type).unwrap();
+        assert_eq!(json, r#"{"Custom":"alias"}"#);
+        assert_eq!(
+            serde_json::from_str::<MatchType>(&json).unwrap(),
+            match_type
+        );
+    }
+}
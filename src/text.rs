@@ -0,0 +1,535 @@
+//! Finding and rewriting ID references in free text.
+//!
+//! The scanner splits text into word-like tokens and runs each one through
+//! [`parse_id`], so it accepts exactly the IDs the parser does. Restricting the
+//! scan to known prefixes keeps ordinary words like `utf-8` from matching;
+//! [`ScanOptions`] adds opt-in rules for prose, such as skipping email
+//! addresses.
+
+use crate::parse::{ParsedId, parse_id};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Characters trimmed from the start of a token before parsing: ellipses
+/// (`...bd-a7x`) and Markdown emphasis (`_bd-a7x_`). A leading dash is kept, so
+/// that `-bd-a7x` stays a single word that is not an ID.
+const TRIM_START: &[char] = &['.', '_'];
+
+/// Characters trimmed from the end of a token before parsing: sentence
+/// punctuation (`bd-a7x.`), dashes and Markdown emphasis.
+const TRIM_END: &[char] = &['.', '-', '_'];
+
+/// Extra rules for [`find_ids_in_text_with`], on top of the `parse_id` grammar.
+///
+/// All rules are off by default. They skip words that parse as IDs but are
+/// unlikely to be references in prose.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::{ScanOptions, find_ids_in_text, find_ids_in_text_with};
+///
+/// let text = "send an e-mail to foo@bd-a7x about bd-k2m";
+/// assert_eq!(find_ids_in_text(text, &[]).len(), 3);
+///
+/// let found = find_ids_in_text_with(text, &[], &ScanOptions::prose());
+/// assert_eq!(found.len(), 1);
+/// assert_eq!(found[0].text, "bd-k2m");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScanOptions {
+    /// Skip words that start with a dash, like the flags `-bd-a7x` and `--bd-a7x`.
+    pub skip_flags: bool,
+    /// Skip words right after an `@`, like the address `foo@bd-a7x`.
+    pub skip_emails: bool,
+    /// Skip hashes of four or more letters without a digit, which the generator
+    /// never produces, like `e-mail`.
+    pub require_digit: bool,
+}
+
+impl ScanOptions {
+    /// No extra rules: every word that parses as an ID matches.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            skip_flags: false,
+            skip_emails: false,
+            require_digit: false,
+        }
+    }
+
+    /// All extra rules, for scanning prose without a prefix filter.
+    #[must_use]
+    pub const fn prose() -> Self {
+        Self {
+            skip_flags: true,
+            skip_emails: true,
+            require_digit: true,
+        }
+    }
+
+    /// Sets whether words that start with a dash are skipped.
+    #[must_use]
+    pub const fn skip_flags(mut self, skip: bool) -> Self {
+        self.skip_flags = skip;
+        self
+    }
+
+    /// Sets whether words right after an `@` are skipped.
+    #[must_use]
+    pub const fn skip_emails(mut self, skip: bool) -> Self {
+        self.skip_emails = skip;
+        self
+    }
+
+    /// Sets whether hashes of four or more letters need a digit.
+    #[must_use]
+    pub const fn require_digit(mut self, require: bool) -> Self {
+        self.require_digit = require;
+        self
+    }
+}
+
+/// An ID reference found in text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdMatch<'a> {
+    /// The reference exactly as written.
+    pub text: &'a str,
+    /// Byte range of `text` within the scanned string.
+    pub span: Range<usize>,
+    /// The parsed (lowercased) ID.
+    pub id: ParsedId,
+}
+
+/// Finds ID references in `text`.
+///
+/// A reference is a whole word: it is delimited by characters that cannot
+/// appear in an ID (whitespace, most punctuation), so `xbd-a7x` does not
+/// contain `bd-a7x`. Leading dots and underscores and trailing dots, dashes and
+/// underscores are not part of the reference. Every remaining word that
+/// [`parse_id`] accepts is a reference.
+///
+/// Only IDs whose prefix is in `prefixes` (case-insensitively) are returned. An
+/// empty `prefixes` accepts any prefix, so hyphenated words like `e-mail` match
+/// too; see [`find_ids_in_text_with`] for rules that skip them.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::find_ids_in_text;
+///
+/// let text = "Fixes bd-a7x3q9 and tk-r2m.1. Requires utf-8.";
+/// let found = find_ids_in_text(text, &["bd", "tk"]);
+///
+/// let ids: Vec<&str> = found.iter().map(|m| m.text).collect();
+/// assert_eq!(ids, ["bd-a7x3q9", "tk-r2m.1"]);
+/// assert_eq!(&text[found[1].span.clone()], "tk-r2m.1");
+/// assert_eq!(found[1].id.child_path, [1]);
+/// ```
+#[must_use]
+pub fn find_ids_in_text<'a>(text: &'a str, prefixes: &[&str]) -> Vec<IdMatch<'a>> {
+    find_ids_in_text_with(text, prefixes, &ScanOptions::new())
+}
+
+/// Finds ID references in `text`, skipping the words `options` rules out.
+///
+/// Works like [`find_ids_in_text`] with the extra rules of [`ScanOptions`]
+/// applied, whether or not `prefixes` is empty.
+#[must_use]
+pub fn find_ids_in_text_with<'a>(
+    text: &'a str,
+    prefixes: &[&str],
+    options: &ScanOptions,
+) -> Vec<IdMatch<'a>> {
+    words(text)
+        .filter_map(|(word, span)| {
+            if !word.contains('-')
+                || (options.skip_flags && word.starts_with('-'))
+                || (options.skip_emails && text[..span.start].ends_with('@'))
+            {
+                return None;
+            }
+            let id = parse_id(word).ok()?;
+            if options.require_digit
+                && id.hash.len() >= 4
+                && !id.hash.bytes().any(|b| b.is_ascii_digit())
+            {
+                return None;
+            }
+            if !prefixes.is_empty() && !prefixes.iter().any(|p| p.eq_ignore_ascii_case(&id.prefix))
+            {
+                return None;
+            }
+            Some(IdMatch {
+                text: word,
+                span,
+                id,
+            })
+        })
+        .collect()
+}
+
+/// Rewrites ID references in `text`, keeping everything else byte-for-byte.
+///
+/// `rewrite` is called for every ID [`find_ids_in_text`] finds with the same
+/// `prefixes` (any prefix if empty) along with its byte span, and returns the
+/// replacement or `None` to keep the reference as written.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::rewrite_ids;
+///
+/// let text = "Fixes bd-a7x.1, see utf-8.";
+/// let linked = rewrite_ids(text, &["bd"], |id, span| {
+///     Some(format!("[{}](https://example.com/{id})", &text[span]))
+/// });
+/// assert_eq!(linked, "Fixes [bd-a7x.1](https://example.com/bd-a7x.1), see utf-8.");
+/// ```
+pub fn rewrite_ids<F>(text: &str, prefixes: &[&str], mut rewrite: F) -> String
+where
+    F: FnMut(&ParsedId, Range<usize>) -> Option<String>,
+{
+    splice(
+        text,
+        find_ids_in_text(text, prefixes)
+            .into_iter()
+            .filter_map(|m| rewrite(&m.id, m.span.clone()).map(|new| (m.span, new))),
+    )
+}
+
+/// Rewrites whole words in `text`, keeping everything else byte-for-byte.
+///
+/// Words are split and trimmed exactly as in [`find_ids_in_text`], but need not
+/// be valid IDs. Use this for short forms that are not IDs by themselves, such
+/// as expanding `a7x` to `bd-a7x3q9` through an
+/// [`IdResolver`](crate::resolve::IdResolver).
+///
+/// # Examples
+///
+/// ```
+/// use terseid::rewrite_words;
+///
+/// let expanded = rewrite_words("see a7x.", |word, _| {
+///     (word == "a7x").then(|| "bd-a7x3q9".to_string())
+/// });
+/// assert_eq!(expanded, "see bd-a7x3q9.");
+/// ```
+pub fn rewrite_words<F>(text: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&str, Range<usize>) -> Option<String>,
+{
+    splice(
+        text,
+        words(text).filter_map(|(word, span)| rewrite(word, span.clone()).map(|new| (span, new))),
+    )
+}
+
+/// Splits `text` into trimmed, non-empty words with their byte spans.
+fn words(text: &str) -> impl Iterator<Item = (&str, Range<usize>)> {
+    // A trailing sentinel closes a word that runs to the end of the text.
+    text.char_indices()
+        .chain(core::iter::once((text.len(), ' ')))
+        .scan(None, |word_start, (i, c)| {
+            Some(match (*word_start, is_id_char(c)) {
+                (None, true) => {
+                    *word_start = Some(i);
+                    None
+                }
+                (Some(start), false) => {
+                    *word_start = None;
+                    trim_word(text, start..i)
+                }
+                _ => None,
+            })
+        })
+        .flatten()
+}
+
+/// Characters that can appear inside an ID, plus `_` so that identifiers like
+/// `foo_bd-a7x` stay one word.
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '.' | '_')
+}
+
+fn trim_word(text: &str, span: Range<usize>) -> Option<(&str, Range<usize>)> {
+    let token = &text[span.clone()];
+    let trimmed_start = token.trim_start_matches(TRIM_START);
+    let trimmed = trimmed_start.trim_end_matches(TRIM_END);
+    if trimmed.is_empty() {
+        return None;
+    }
+    let start = span.start + (token.len() - trimmed_start.len());
+    Some((trimmed, start..start + trimmed.len()))
+}
+
+/// Copies `text`, replacing each (ascending, non-overlapping) span.
+fn splice(text: &str, replacements: impl Iterator<Item = (Range<usize>, String)>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (span, replacement) in replacements {
+        out.push_str(&text[copied..span.start]);
+        out.push_str(&replacement);
+        copied = span.end;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(text: &'a str, prefixes: &[&str]) -> Vec<&'a str> {
+        find_ids_in_text(text, prefixes)
+            .into_iter()
+            .map(|m| m.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_finds_ids_with_spans() {
+        let text = "see bd-a7x3q9, then tk-r2m.1.3";
+        let found = find_ids_in_text(text, &["bd", "tk"]);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].span, 4..13);
+        assert_eq!(found[1].span, 20..30);
+        for m in &found {
+            assert_eq!(&text[m.span.clone()], m.text);
+            assert_eq!(m.id, parse_id(m.text).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_prefix_filter_avoids_false_positives() {
+        let text = "utf-8 encoded, x86-64 build, fixes bd-a7x";
+        assert_eq!(texts(text, &["bd"]), vec!["bd-a7x"]);
+        assert_eq!(texts(text, &[]), vec!["utf-8", "x86-64", "bd-a7x"]);
+    }
+
+    #[test]
+    fn test_trailing_punctuation() {
+        let text = "Done: bd-a7x. Also (bd-b8y.2), bd-k2m.1.! and \"bd-c9z\"; bd-d0e...";
+        assert_eq!(
+            texts(text, &["bd"]),
+            vec!["bd-a7x", "bd-b8y.2", "bd-k2m.1", "bd-c9z", "bd-d0e"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_wrappers() {
+        let text = "**bd-a7x** _bd-b8y_ `bd-c9z` [bd-d0e](http://x/bd-e1f)";
+        assert_eq!(
+            texts(text, &["bd"]),
+            vec!["bd-a7x", "bd-b8y", "bd-c9z", "bd-d0e", "bd-e1f"]
+        );
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        let text = "xbd-a7x foo_bd-a7x pre-bd-a7x bd-a7x";
+        assert_eq!(texts(text, &["bd"]), vec!["bd-a7x"]);
+        assert_eq!(find_ids_in_text(text, &["bd"])[0].span, 30..36);
+    }
+
+    fn prose_texts<'a>(text: &'a str, prefixes: &[&str]) -> Vec<&'a str> {
+        find_ids_in_text_with(text, prefixes, &ScanOptions::prose())
+            .into_iter()
+            .map(|m| m.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_hyphen_led_words() {
+        // A leading dash is kept, so these parse with prefix `-bd` or `--bd`.
+        let text = "-bd-a7x --bd-b8y opt-bd-c9z -- bd-d0e";
+        assert_eq!(texts(text, &["bd"]), vec!["bd-d0e"]);
+        assert_eq!(
+            texts(text, &[]),
+            vec!["-bd-a7x", "--bd-b8y", "opt-bd-c9z", "bd-d0e"]
+        );
+        assert_eq!(prose_texts(text, &[]), vec!["opt-bd-c9z", "bd-d0e"]);
+        assert_eq!(prose_texts("-bd-a7x", &["-bd"]), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_email_like_words() {
+        let text = "mail foo@bd-a7x or @bd-b8y, send an e-mail to bd-c9z";
+        assert_eq!(texts(text, &["bd"]), vec!["bd-a7x", "bd-b8y", "bd-c9z"]);
+        assert_eq!(
+            texts(text, &[]),
+            vec!["bd-a7x", "bd-b8y", "e-mail", "bd-c9z"]
+        );
+        assert_eq!(prose_texts(text, &["bd"]), vec!["bd-c9z"]);
+        assert_eq!(prose_texts(text, &[]), vec!["bd-c9z"]);
+        // The digit rule applies with a prefix filter too.
+        assert_eq!(prose_texts("send an e-mail", &["e"]), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_scan_options_individually() {
+        let text = "-bd-a7x foo@bd-b8y bd-abcd";
+        let with = |options: ScanOptions| -> Vec<&str> {
+            find_ids_in_text_with(text, &[], &options)
+                .into_iter()
+                .map(|m| m.text)
+                .collect()
+        };
+        assert_eq!(
+            with(ScanOptions::new()),
+            vec!["-bd-a7x", "bd-b8y", "bd-abcd"]
+        );
+        assert_eq!(
+            with(ScanOptions::new().skip_flags(true)),
+            vec!["bd-b8y", "bd-abcd"]
+        );
+        assert_eq!(
+            with(ScanOptions::new().skip_emails(true)),
+            vec!["-bd-a7x", "bd-abcd"]
+        );
+        assert_eq!(
+            with(ScanOptions::new().require_digit(true)),
+            vec!["-bd-a7x", "bd-b8y"]
+        );
+        assert_eq!(ScanOptions::default(), ScanOptions::new());
+    }
+
+    #[test]
+    fn test_hyphenated_prefix() {
+        let text = "in my-proj-a7x3q9.2 today";
+        let found = find_ids_in_text(text, &["my-proj"]);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id.prefix, "my-proj");
+        assert_eq!(found[0].id.child_path, vec![2]);
+    }
+
+    #[test]
+    fn test_same_grammar_as_parse_id() {
+        // Invalid child segments make the whole word invalid, as in parse_id.
+        assert!(texts("bd-a7x.txt bd-a7x.1x", &["bd"]).is_empty());
+        // Case-insensitive, like parse_id.
+        let found = find_ids_in_text("BD-A7X", &["bd"]);
+        assert_eq!(found[0].text, "BD-A7X");
+        assert_eq!(found[0].id.to_id_string(), "bd-a7x");
+        assert_eq!(texts("BD-A7X", &["BD"]), vec!["BD-A7X"]);
+    }
+
+    #[test]
+    fn test_non_ascii_text() {
+        let text = "€ bd-a7x — done";
+        let found = find_ids_in_text(text, &["bd"]);
+        assert_eq!(found[0].span, 4..10);
+        assert_eq!(&text[found[0].span.clone()], "bd-a7x");
+    }
+
+    #[test]
+    fn test_empty_and_no_ids() {
+        assert!(find_ids_in_text("", &[]).is_empty());
+        assert!(find_ids_in_text("nothing here - at all", &[]).is_empty());
+    }
+
+    // ========== rewrite_ids / rewrite_words ==========
+
+    #[test]
+    fn test_rewrite_ids_keeps_surrounding_text() {
+        let text = "  Fixes BD-A7X.1,\tand (bd-b8y)…\n";
+        let out = rewrite_ids(text, &[], |id, _| Some(id.to_id_string().to_uppercase()));
+        assert_eq!(out, "  Fixes BD-A7X.1,\tand (BD-B8Y)…\n");
+    }
+
+    #[test]
+    fn test_rewrite_ids_none_keeps_original() {
+        let text = "bd-a7x utf-8 tk-r2m";
+        assert_eq!(rewrite_ids(text, &[], |_, _| None), text);
+        let out = rewrite_ids(text, &[], |id, _| {
+            (id.prefix == "tk").then(|| "tk-new".into())
+        });
+        assert_eq!(out, "bd-a7x utf-8 tk-new");
+    }
+
+    #[test]
+    fn test_rewrite_ids_prefix_filter() {
+        // Letter-only hashes parse, so a prefix filter lets legacy IDs through.
+        let text = "bd-abcd and tk-efgh, see utf-8";
+        let upper = |id: &ParsedId, _| Some(id.to_id_string().to_uppercase());
+        assert_eq!(
+            rewrite_ids(text, &["bd"], upper),
+            "BD-ABCD and tk-efgh, see utf-8"
+        );
+        assert_eq!(
+            rewrite_ids(text, &[], upper),
+            "BD-ABCD and TK-EFGH, see UTF-8"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_ids_with_remap() {
+        let remap = crate::remap::reparent("bd-a7x.3", "bd-k2m", &["bd-k2m.4"]).unwrap();
+        let text = "Moved bd-a7x.3.1 (was under bd-a7x).";
+        let out = rewrite_ids(text, &[], |id, _| {
+            remap.apply_parsed(id).map(|new| new.to_id_string())
+        });
+        assert_eq!(out, "Moved bd-k2m.5.1 (was under bd-a7x).");
+    }
+
+    #[test]
+    fn test_rewrite_ids_spans_point_into_text() {
+        let text = "a bd-a7x b bd-b8y.";
+        let mut spans = Vec::new();
+        let out = rewrite_ids(text, &[], |_, span| {
+            spans.push(span);
+            None
+        });
+        assert_eq!(out, text);
+        assert_eq!(spans, vec![2..8, 11..17]);
+    }
+
+    #[test]
+    fn test_rewrite_words_expands_short_forms() {
+        use crate::resolve::{IdResolver, ResolverConfig, find_matching_ids};
+        use alloc::string::ToString;
+
+        let known = vec!["bd-a7x3q9".to_string(), "bd-r2m4k1".to_string()];
+        let resolver = IdResolver::new(ResolverConfig::new("bd"));
+        let out = rewrite_words("see a7x and r2m4, not zzz.", |word, _| {
+            resolver
+                .resolve(
+                    word,
+                    |id| known.iter().any(|k| k == id),
+                    |sub| find_matching_ids(&known, sub),
+                )
+                .ok()
+                .map(|resolved| resolved.id)
+        });
+        assert_eq!(out, "see bd-a7x3q9 and bd-r2m4k1, not zzz.");
+    }
+
+    #[test]
+    fn test_rewrite_empty() {
+        assert_eq!(rewrite_ids("", &[], |_, _| Some("x".into())), "");
+        assert_eq!(rewrite_words("...", |_, _| Some("x".into())), "...");
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::proptest;
+
+        proptest! {
+            #[test]
+            fn matches_agree_with_parse_id(text in "[a-zA-Z0-9 .,_()-]{0,40}") {
+                for m in find_ids_in_text(&text, &[]) {
+                    assert_eq!(&text[m.span.clone()], m.text);
+                    assert_eq!(parse_id(m.text).unwrap(), m.id);
+                }
+            }
+
+            #[test]
+            fn rewrite_identity_is_lossless(text in "[a-zA-Z0-9 .,_()-]{0,40}") {
+                let out = rewrite_ids(&text, &[], |_, span| Some(text[span].into()));
+                assert_eq!(out, text);
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! Hierarchies built from flat lists of IDs.
+//!
+//! [`IdTree`] parses each ID once and links children to parents, so tree queries
+//! don't need to re-scan the whole list. Because `ParsedId` orders a parent directly
+//! before its descendants, every subtree is a contiguous range of the tree.
+
+use crate::parse::{ParsedId, parse_id};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Bound;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    placeholder: bool,
+    children: Vec<ParsedId>,
+}
+
+/// A forest of IDs linked by their child paths.
+///
+/// # Examples
+///
+/// ```
+/// use terseid::IdTree;
+///
+/// let tree: IdTree = ["bd-a7x", "bd-a7x.1", "bd-a7x.2", "bd-a7x.1.1", "bd-k2m.3"]
+///     .into_iter()
+///     .collect();
+///
+/// let children: Vec<String> = tree.children("bd-a7x").map(|id| id.to_string()).collect();
+/// assert_eq!(children, ["bd-a7x.1", "bd-a7x.2"]);
+/// assert_eq!(tree.descendants("bd-a7x").count(), 3);
+/// assert_eq!(tree.orphans().next().unwrap().to_string(), "bd-k2m.3");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IdTree {
+    nodes: BTreeMap<ParsedId, Node>,
+    invalid: Vec<String>,
+}
+
+impl IdTree {
+    /// Creates an empty tree.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a tree from a list of IDs.
+    ///
+    /// IDs that fail to parse are skipped and reported by [`invalid`](Self::invalid).
+    /// Duplicates (including case variants) are merged.
+    pub fn from_ids<I>(ids: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut tree = Self::new();
+        for id in ids {
+            match parse_id(id.as_ref()) {
+                Ok(parsed) => {
+                    tree.nodes.entry(parsed).or_default();
+                }
+                Err(_) => tree.invalid.push(String::from(id.as_ref())),
+            }
+        }
+        tree.link();
+        tree
+    }
+
+    /// Returns the number of IDs in the tree, including placeholders.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the tree has no IDs.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns true if `id` is in the tree (as a real ID or a placeholder).
+    #[must_use]
+    pub fn contains(&self, id: &str) -> bool {
+        self.node(id).is_some()
+    }
+
+    /// Returns true if `id` was added by [`add_placeholders`](Self::add_placeholders).
+    #[must_use]
+    pub fn is_placeholder(&self, id: &str) -> bool {
+        self.node(id).is_some_and(|node| node.placeholder)
+    }
+
+    /// Returns the inputs that could not be parsed as IDs.
+    #[must_use]
+    pub fn invalid(&self) -> &[String] {
+        &self.invalid
+    }
+
+    /// Iterates over every ID in the tree in natural order.
+    pub fn iter(&self) -> impl Iterator<Item = &ParsedId> {
+        self.nodes.keys()
+    }
+
+    /// Iterates over root IDs (IDs without a child path).
+    ///
+    /// Orphans are not roots, so a walk from the roots misses them. List them
+    /// with [`orphans`](Self::orphans), or call
+    /// [`add_placeholders`](Self::add_placeholders) first to make every ID
+    /// reachable from a root.
+    pub fn roots(&self) -> impl Iterator<Item = &ParsedId> {
+        self.nodes.keys().filter(|id| id.is_root())
+    }
+
+    /// Iterates over the direct children of `id`.
+    pub fn children(&self, id: &str) -> impl Iterator<Item = &ParsedId> {
+        self.node(id)
+            .into_iter()
+            .flat_map(|node| node.children.iter())
+    }
+
+    /// Iterates over all descendants of `id` in depth-first order.
+    ///
+    /// Works even when `id` itself is not in the tree, so the descendants of a
+    /// missing parent can still be listed.
+    pub fn descendants(&self, id: &str) -> impl Iterator<Item = &ParsedId> {
+        parse_id(id)
+            .ok()
+            .into_iter()
+            .flat_map(|ancestor| self.descendants_of(ancestor))
+    }
+
+    /// Iterates over the other children of `id`'s parent.
+    ///
+    /// Root IDs have no siblings. Orphans are siblings of the other children of
+    /// the same missing parent.
+    pub fn siblings(&self, id: &str) -> impl Iterator<Item = &ParsedId> {
+        parse_id(id).ok().into_iter().flat_map(|this| {
+            let depth = this.depth();
+            parent_of(&this)
+                .into_iter()
+                .flat_map(|parent| self.descendants_of(parent))
+                .filter(move |candidate| candidate.depth() == depth && **candidate != this)
+        })
+    }
+
+    /// Iterates over IDs whose parent is not in the tree.
+    pub fn orphans(&self) -> impl Iterator<Item = &ParsedId> {
+        self.nodes
+            .keys()
+            .filter(|id| parent_of(id).is_some_and(|parent| !self.nodes.contains_key(&parent)))
+    }
+
+    /// Adds placeholder parents so that every orphan becomes reachable from a root.
+    ///
+    /// Returns the number of placeholders added.
+    pub fn add_placeholders(&mut self) -> usize {
+        let mut missing = Vec::new();
+        for id in self.nodes.keys() {
+            let mut current = parent_of(id);
+            while let Some(parent) = current {
+                if self.nodes.contains_key(&parent) {
+                    break;
+                }
+                current = parent_of(&parent);
+                missing.push(parent);
+            }
+        }
+
+        let before = self.nodes.len();
+        for id in missing {
+            self.nodes.entry(id).or_insert_with(|| Node {
+                placeholder: true,
+                children: Vec::new(),
+            });
+        }
+        self.link();
+        self.nodes.len() - before
+    }
+
+    /// Subtree of `ancestor`: the contiguous range of keys right after it.
+    fn descendants_of(&self, ancestor: ParsedId) -> impl Iterator<Item = &ParsedId> {
+        self.nodes
+            .range((Bound::Excluded(&ancestor), Bound::Unbounded))
+            .map(|(id, _)| id)
+            .take_while(move |id| is_descendant(id, &ancestor))
+    }
+
+    fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.get(&parse_id(id).ok()?)
+    }
+
+    /// Rebuilds every node's child list. Iteration order keeps children sorted.
+    fn link(&mut self) {
+        let edges: Vec<(ParsedId, ParsedId)> = self
+            .nodes
+            .keys()
+            .filter_map(|id| parent_of(id).map(|parent| (parent, id.clone())))
+            .collect();
+
+        for node in self.nodes.values_mut() {
+            node.children.clear();
+        }
+        for (parent, child) in edges {
+            if let Some(node) = self.nodes.get_mut(&parent) {
+                node.children.push(child);
+            }
+        }
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for IdTree {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self::from_ids(iter)
+    }
+}
+
+fn parent_of(id: &ParsedId) -> Option<ParsedId> {
+    if id.is_root() {
+        return None;
+    }
+    let mut parent = id.clone();
+    parent.child_path.pop();
+    Some(parent)
+}
+
+fn is_descendant(id: &ParsedId, ancestor: &ParsedId) -> bool {
+    id.prefix == ancestor.prefix
+        && id.hash == ancestor.hash
+        && id.child_path.len() > ancestor.child_path.len()
+        && id.child_path.starts_with(&ancestor.child_path)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn strings<'a>(ids: impl Iterator<Item = &'a ParsedId>) -> Vec<String> {
+        ids.map(ToString::to_string).collect()
+    }
+
+    fn sample() -> IdTree {
+        IdTree::from_ids([
+            "bd-a7x",
+            "bd-a7x.1",
+            "bd-a7x.2",
+            "bd-a7x.10",
+            "bd-a7x.1.1",
+            "bd-a7x.1.2",
+            "bd-b8y",
+            "bd-k2m.3",
+            "bd-k2m.3.1",
+            "bd-k2m.4",
+        ])
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = IdTree::new();
+        assert!(tree.is_empty());
+        assert_eq!(tree.roots().count(), 0);
+        assert_eq!(tree.children("bd-a7x").count(), 0);
+    }
+
+    #[test]
+    fn test_len_and_contains() {
+        let tree = sample();
+        assert_eq!(tree.len(), 10);
+        assert!(tree.contains("BD-A7X.1"));
+        assert!(!tree.contains("bd-a7x.3"));
+    }
+
+    #[test]
+    fn test_roots() {
+        assert_eq!(strings(sample().roots()), vec!["bd-a7x", "bd-b8y"]);
+    }
+
+    #[test]
+    fn test_children_sorted_numerically() {
+        let tree = sample();
+        assert_eq!(
+            strings(tree.children("bd-a7x")),
+            vec!["bd-a7x.1", "bd-a7x.2", "bd-a7x.10"]
+        );
+        assert_eq!(
+            strings(tree.children("bd-a7x.1")),
+            vec!["bd-a7x.1.1", "bd-a7x.1.2"]
+        );
+        assert_eq!(tree.children("bd-b8y").count(), 0);
+        assert_eq!(tree.children("not an id").count(), 0);
+    }
+
+    #[test]
+    fn test_descendants_depth_first() {
+        let tree = sample();
+        assert_eq!(
+            strings(tree.descendants("bd-a7x")),
+            vec![
+                "bd-a7x.1",
+                "bd-a7x.1.1",
+                "bd-a7x.1.2",
+                "bd-a7x.2",
+                "bd-a7x.10"
+            ]
+        );
+        assert_eq!(tree.descendants("bd-b8y").count(), 0);
+    }
+
+    #[test]
+    fn test_descendants_of_missing_parent() {
+        let tree = sample();
+        assert_eq!(
+            strings(tree.descendants("bd-k2m")),
+            vec!["bd-k2m.3", "bd-k2m.3.1", "bd-k2m.4"]
+        );
+    }
+
+    #[test]
+    fn test_siblings() {
+        let tree = sample();
+        assert_eq!(
+            strings(tree.siblings("bd-a7x.2")),
+            vec!["bd-a7x.1", "bd-a7x.10"]
+        );
+        assert_eq!(strings(tree.siblings("bd-a7x.1.1")), vec!["bd-a7x.1.2"]);
+        assert_eq!(tree.siblings("bd-a7x").count(), 0);
+        assert_eq!(strings(tree.siblings("bd-k2m.3")), vec!["bd-k2m.4"]);
+    }
+
+    #[test]
+    fn test_orphans() {
+        let tree = sample();
+        assert_eq!(strings(tree.orphans()), vec!["bd-k2m.3", "bd-k2m.4"]);
+    }
+
+    #[test]
+    fn test_only_orphans() {
+        let mut tree = IdTree::from_ids(["bd-a7x.1", "bd-a7x.2"]);
+        assert_eq!(tree.roots().count(), 0);
+        assert_eq!(strings(tree.orphans()), vec!["bd-a7x.1", "bd-a7x.2"]);
+
+        assert_eq!(tree.add_placeholders(), 1);
+        assert_eq!(strings(tree.roots()), vec!["bd-a7x"]);
+        assert_eq!(
+            strings(tree.children("bd-a7x")),
+            vec!["bd-a7x.1", "bd-a7x.2"]
+        );
+    }
+
+    #[test]
+    fn test_add_placeholders() {
+        let mut tree = IdTree::from_ids(["bd-a7x", "bd-k2m.3.1.5", "bd-a7x.2.1"]);
+        assert_eq!(tree.add_placeholders(), 4);
+        assert_eq!(tree.orphans().count(), 0);
+        assert_eq!(strings(tree.roots()), vec!["bd-a7x", "bd-k2m"]);
+        assert!(tree.is_placeholder("bd-k2m"));
+        assert!(tree.is_placeholder("bd-k2m.3.1"));
+        assert!(tree.is_placeholder("bd-a7x.2"));
+        assert!(!tree.is_placeholder("bd-a7x"));
+        assert_eq!(strings(tree.children("bd-k2m.3.1")), vec!["bd-k2m.3.1.5"]);
+        assert_eq!(strings(tree.children("bd-a7x")), vec!["bd-a7x.2"]);
+        assert_eq!(tree.add_placeholders(), 0);
+    }
+
+    #[test]
+    fn test_invalid_and_duplicates() {
+        let tree: IdTree = ["bd-a7x", "BD-A7X", "nope", "bd-a7x.x"]
+            .into_iter()
+            .collect();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.invalid(), ["nope", "bd-a7x.x"]);
+    }
+
+    #[test]
+    fn test_iter_natural_order() {
+        let tree = IdTree::from_ids(["bd-a7x.10", "bd-a7x.2", "bd-a7x"]);
+        assert_eq!(
+            strings(tree.iter()),
+            vec!["bd-a7x", "bd-a7x.2", "bd-a7x.10"]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Strongly-typed ID newtypes.
+//!
+//! [`define_id!`](crate::define_id) generates a newtype around a validated ID string
+//! that is bound to a single prefix, so IDs from different namespaces cannot be mixed up.
+
+/// Defines a newtype for IDs with a fixed prefix.
+///
+/// The generated type wraps the canonical (lowercased) ID string together with
+/// its [`ParsedId`](crate::ParsedId), and implements `FromStr`, `Display`,
+/// `AsRef<str>`, `Hash`, and `Ord` using the natural ordering of
+/// [`compare_ids`](crate::compare_ids), without re-parsing on each comparison.
+/// Parsing goes through [`parse_id`](crate::parse_id), so only well-formed IDs
+/// with the declared prefix are accepted.
+///
+/// # Examples
+///
+/// ```
+/// terseid::define_id!(ReviewId, prefix = "cr");
+///
+/// let id: ReviewId = "CR-a7x3q9".parse().unwrap();
+/// assert_eq!(id.as_str(), "cr-a7x3q9");
+/// assert_eq!(id.child(2).to_string(), "cr-a7x3q9.2");
+/// assert!("th-a7x3q9".parse::<ReviewId>().is_err());
+///
+/// let generated = ReviewId::generate(|nonce| format!("seed|{nonce}").into_bytes(), 0, |_| false);
+/// assert!(generated.as_str().starts_with("cr-"));
+/// ```
+///
+/// Different ID kinds are distinct types:
+///
+/// ```compile_fail
+/// terseid::define_id!(ReviewId, prefix = "cr");
+/// terseid::define_id!(ThreadId, prefix = "th");
+///
+/// fn close_thread(_id: &ThreadId) {}
+///
+/// let review: ReviewId = "cr-a7x".parse().unwrap();
+/// close_thread(&review);
+/// ```
+#[macro_export]
+macro_rules! define_id {
+    ($(#[$meta:meta])* $vis:vis $name:ident, prefix = $prefix:literal $(,)?) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        $vis struct $name {
+            id: $crate::__private::String,
+            parsed: $crate::ParsedId,
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// The prefix every ID of this type carries.
+            pub const PREFIX: &'static str = $prefix;
+
+            /// Parses and validates an ID of this type.
+            ///
+            /// # Errors
+            ///
+            /// Returns `InvalidId` if the ID cannot be parsed.
+            /// Returns `PrefixMismatch` if the ID has a different prefix.
+            pub fn parse(id: &str) -> $crate::Result<Self> {
+                let parsed = $crate::parse_id(id)?;
+                if parsed.prefix != Self::PREFIX {
+                    return Err($crate::TerseIdError::PrefixMismatch {
+                        expected: Self::PREFIX.into(),
+                        found: parsed.prefix,
+                    });
+                }
+                Ok(Self::from_parsed(parsed))
+            }
+
+            fn from_parsed(parsed: $crate::ParsedId) -> Self {
+                Self {
+                    id: parsed.to_id_string(),
+                    parsed,
+                }
+            }
+
+            /// Returns the ID as a string slice.
+            #[must_use]
+            pub fn as_str(&self) -> &str {
+                &self.id
+            }
+
+            /// Returns the parsed form of the ID.
+            #[must_use]
+            pub const fn parsed(&self) -> &$crate::ParsedId {
+                &self.parsed
+            }
+
+            /// Consumes the ID and returns the underlying string.
+            #[must_use]
+            pub fn into_string(self) -> $crate::__private::String {
+                self.id
+            }
+
+            /// Returns a default generator configuration for this prefix.
+            #[must_use]
+            pub fn config() -> $crate::IdConfig {
+                $crate::IdConfig::new(Self::PREFIX)
+            }
+
+            /// Returns a generator for this prefix with default settings.
+            #[must_use]
+            pub fn generator() -> $crate::IdGenerator {
+                $crate::IdGenerator::new(Self::config())
+            }
+
+            /// Generates a new ID of this type with collision avoidance.
+            ///
+            /// See [`IdGenerator::generate`]($crate::IdGenerator::generate).
+            pub fn generate<S, F>(seed_fn: S, item_count: usize, exists: F) -> Self
+            where
+                S: Fn(u32) -> $crate::__private::Vec<u8>,
+                F: Fn(&str) -> bool,
+            {
+                let id = Self::generator().generate(seed_fn, item_count, exists);
+                let parsed = $crate::parse_id(&id).unwrap_or_else(|_| $crate::ParsedId {
+                    // The generator's last-resort `{prefix}-{hash}.fallback`
+                    // does not parse; keep it whole, sorted by its text.
+                    prefix: Self::PREFIX.into(),
+                    hash: id[Self::PREFIX.len() + 1..].into(),
+                    child_path: $crate::__private::Vec::new(),
+                });
+                Self { id, parsed }
+            }
+
+            /// Returns the child ID `{self}.{child_number}`.
+            #[must_use]
+            pub fn child(&self, child_number: u32) -> Self {
+                let mut parsed = self.parsed.clone();
+                parsed.child_path.push(child_number);
+                Self::from_parsed(parsed)
+            }
+
+            /// Returns the parent ID, or `None` if this is a root ID.
+            #[must_use]
+            pub fn parent(&self) -> Option<Self> {
+                let mut parsed = self.parsed.clone();
+                parsed.child_path.pop()?;
+                Some(Self::from_parsed(parsed))
+            }
+
+            /// Returns the depth of this ID (number of child path segments).
+            #[must_use]
+            pub const fn depth(&self) -> usize {
+                self.parsed.depth()
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::TerseIdError;
+
+            fn from_str(s: &str) -> $crate::Result<Self> {
+                Self::parse(s)
+            }
+        }
+
+        impl ::core::cmp::Ord for $name {
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                self.parsed.cmp(&other.parsed)
+            }
+        }
+
+        impl ::core::cmp::PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.id).finish()
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(&self.id)
+            }
+        }
+
+        impl ::core::convert::AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.id
+            }
+        }
+
+        impl ::core::convert::From<$name> for $crate::__private::String {
+            fn from(id: $name) -> Self {
+                id.id
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use crate::TerseIdError;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    crate::define_id!(ReviewId, prefix = "cr");
+    crate::define_id!(
+        /// Hyphenated-prefix ID used to exercise the last-dash rule.
+        ProjectId,
+        prefix = "my-proj",
+    );
+
+    #[test]
+    fn test_parse_valid() {
+        let id: ReviewId = "cr-a7x3q9".parse().unwrap();
+        assert_eq!(id.as_str(), "cr-a7x3q9");
+        assert_eq!(ReviewId::PREFIX, "cr");
+    }
+
+    #[test]
+    fn test_parse_normalizes_case() {
+        let id: ReviewId = "CR-A7X.1".parse().unwrap();
+        assert_eq!(id.to_string(), "cr-a7x.1");
+    }
+
+    #[test]
+    fn test_parse_wrong_prefix() {
+        let err = "th-a7x".parse::<ReviewId>().unwrap_err();
+        assert_eq!(
+            err,
+            TerseIdError::PrefixMismatch {
+                expected: "cr".to_string(),
+                found: "th".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(matches!(
+            "cr-a7x.abc".parse::<ReviewId>(),
+            Err(TerseIdError::InvalidId { .. })
+        ));
+    }
+
+    #[test]
+    fn test_hyphenated_prefix() {
+        let id: ProjectId = "my-proj-a7x3q9".parse().unwrap();
+        assert_eq!(id.as_ref(), "my-proj-a7x3q9");
+        assert!("proj-a7x3q9".parse::<ProjectId>().is_err());
+    }
+
+    #[test]
+    fn test_child_and_parent() {
+        let root: ReviewId = "cr-a7x".parse().unwrap();
+        let child = root.child(1);
+        let grandchild = child.child(3);
+        assert_eq!(grandchild.as_str(), "cr-a7x.1.3");
+        assert_eq!(grandchild.depth(), 2);
+        assert_eq!(grandchild.parent(), Some(child));
+        assert_eq!(root.parent(), None);
+    }
+
+    #[test]
+    fn test_generate_uses_prefix() {
+        let id = ReviewId::generate(|nonce| format!("seed-{nonce}").into_bytes(), 0, |_| false);
+        assert!(id.as_str().starts_with("cr-"));
+        assert_eq!(id.as_str().parse::<ReviewId>().unwrap(), id);
+        assert_eq!(ReviewId::generator().prefix(), "cr");
+    }
+
+    #[test]
+    fn test_ord_and_hash() {
+        let mut ids: Vec<ReviewId> = ["cr-b2c", "cr-a7x.10", "cr-a7x", "cr-a7x.2"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        ids.sort();
+        let strs: Vec<&str> = ids.iter().map(ReviewId::as_str).collect();
+        assert_eq!(strs, vec!["cr-a7x", "cr-a7x.2", "cr-a7x.10", "cr-b2c"]);
+
+        let set: std::collections::HashSet<ReviewId> = ids.into_iter().collect();
+        assert!(set.contains(&"cr-a7x".parse::<ReviewId>().unwrap()));
+    }
+
+    #[test]
+    fn test_parsed_kept_with_id() {
+        let id: ReviewId = "CR-A7X.2".parse().unwrap();
+        assert_eq!(id.parsed(), &crate::parse_id("cr-a7x.2").unwrap());
+        assert_eq!(id.child(10).parsed().child_path, vec![2, 10]);
+        assert_eq!(id.parent().unwrap().parsed().child_path, Vec::<u32>::new());
+        assert_eq!(format!("{id:?}"), "ReviewId(\"cr-a7x.2\")");
+    }
+
+    #[test]
+    fn test_into_string() {
+        let id: ReviewId = "cr-a7x".parse().unwrap();
+        let s: String = id.clone().into();
+        assert_eq!(s, "cr-a7x");
+        assert_eq!(id.into_string(), "cr-a7x");
+    }
+}